@@ -1,71 +1,40 @@
 use crate::emulator::cpu::Cpu;
-use crate::emulator::memory::Memory;
-
-enum AddressingType {
-    Implied,
-    AbsoluteJSR,
-    AbsoluteIndexedReadX,
-    AbsoluteIndexedReadY,
-    Relative,
-    ImpliedRTS,
-    ImpliedRTI,
-    Immediate,
-    AbsoluteWrite,
-    ZeroPageWrite,
-    AbsoluteRead,
-    AbsoluteReadModifyWrite,
-    AbsoluteJMP,
-    AbsoluteIndexedWriteX,
-    AbsoluteIndexedWriteY,
-    ZeroPageReadModifyWrite,
-    IndirectIndexedRead,
-    IndirectIndexedWrite,
-    Accumulator,
-    ZeroPageRead,
-    ZeroPageIndexedWriteX,
-    // ZeroPageIndexedWriteY,
-    ZeroPageIndexedReadX,
-    // ZeroPageIndexedReadY,
-    ZeroPageIndexedReadModifyWriteX,
-    // ZeroPageIndexedReadModifyWriteY,
-    AbsoluteIndirectJMP,
-    ImpliedPHA,
-    ImpliedPLA,
-    ImpliedIRQ,
-}
+use crate::emulator::bus::Bus;
+use crate::emulator::opcodes::OPCODES;
 
 pub struct Addressing {
-    addressing_type: AddressingType,
     operand1: Option<u8>,
     operand2: Option<u8>,
     low: u8,
     high: u8,
     fix_high: bool,
     latch: u8,
+    vector: u16,
+    interrupt_poll: bool,
 }
 
 impl Addressing {
     pub fn new() -> Self {
         Self {
-            addressing_type: AddressingType::Immediate,
             operand1: None,
             operand2: None,
             low: 0,
             high: 0,
             fix_high: false,
             latch: 0,
+            vector: 0xFFFE,
+            interrupt_poll: false,
         }
     }
 
-    fn set_addressing_type(&mut self, addressing_type: AddressingType) {
-        self.addressing_type = addressing_type;
+    fn clear_operands(&mut self) {
         self.operand1 = None;
         self.operand2 = None;
     }
 
-    fn read_operand(&mut self, memory: &mut Memory, cpu: &mut Cpu) -> u8 {
+    fn read_operand(&mut self, memory: &mut impl Bus, cpu: &mut Cpu) -> u8 {
         let value = memory.get_from_word(cpu.get_and_increment_pc());
-        if self.operand1 == None {
+        if self.operand1.is_none() {
             self.operand1 = Some(value);
         } else {
             self.operand2 = Some(value);
@@ -81,104 +50,9 @@ impl Addressing {
         self.operand2
     }
 
-    pub fn description(&self) -> String {
-        match self.addressing_type {
-            AddressingType::Immediate => {
-                format!("#${:02X}", self.operand1.unwrap())
-            }
-            AddressingType::Implied => {
-                String::from("")
-            }
-            AddressingType::AbsoluteJSR => {
-                format!("${:02X}{:02X}", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteIndexedReadX => {
-                format!("${:02X}{:02X},X", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteIndexedReadY => {
-                format!("${:02X}{:02X},Y", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::Relative => {
-                format!("${:02X}{:02X}", self.high, self.low)
-            }
-            AddressingType::ImpliedRTS => {
-                String::from("")
-            }
-            AddressingType::ImpliedRTI => {
-                String::from("")
-            }
-            AddressingType::AbsoluteWrite => {
-                format!("${:02X}{:02X}", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::ZeroPageWrite => {
-                format!("#${:02X}", self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteRead => {
-                format!("${:02X}{:02X}", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteReadModifyWrite => {
-                format!("${:02X}{:02X}", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteJMP => {
-                format!("${:02X}{:02X}", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteIndexedWriteX => {
-                format!("${:02X}{:02X},X", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::AbsoluteIndexedWriteY => {
-                format!("${:02X}{:02X},Y", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::ZeroPageReadModifyWrite => {
-                format!("${:02X}", self.operand1.unwrap())
-            }
-            AddressingType::IndirectIndexedRead => {
-                format!("(${:02X}),Y", self.operand1.unwrap())
-            }
-            AddressingType::IndirectIndexedWrite => {
-                format!("(${:02X}),Y", self.operand1.unwrap())
-            }
-            AddressingType::Accumulator => {
-                String::from("")
-            }
-            AddressingType::ZeroPageRead => {
-                format!("#${:02X}", self.operand1.unwrap())
-            }
-            AddressingType::ZeroPageIndexedWriteX => {
-                format!("${:02X},X", self.operand1.unwrap())
-            }
-            // AddressingType::ZeroPageIndexedWriteY => {
-            //     format!("${:02X},Y", self.operand1.unwrap())
-            // }
-            AddressingType::ZeroPageIndexedReadX => {
-                format!("${:02X},X", self.operand1.unwrap())
-            }
-            // AddressingType::ZeroPageIndexedReadY => {
-            //     format!("${:02X},Y", self.operand1.unwrap())
-            // }
-            AddressingType::AbsoluteIndirectJMP => {
-                format!("(${:02X}{:02X})", self.operand2.unwrap(), self.operand1.unwrap())
-            }
-            AddressingType::ImpliedPHA => {
-                String::from("")
-            }
-            AddressingType::ImpliedPLA => {
-                String::from("")
-            }
-            AddressingType::ZeroPageIndexedReadModifyWriteX => {
-                format!("${:02X},X", self.operand1.unwrap())
-            }
-            // AddressingType::ZeroPageIndexedReadModifyWriteY => {
-            //     format!("${:02X},Y", self.operand1.unwrap())
-            // }
-            AddressingType::ImpliedIRQ => {
-                String::from("")
-            }
-        }
-    }
-
-    pub fn immediate(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn immediate(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::Immediate);
+            self.clear_operands();
             let value = self.read_operand(memory, cpu);
             inst(cpu, value);
             return Ok(1);
@@ -186,22 +60,24 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn implied(&mut self, sub_tick: u8, cpu: &mut Cpu, inst: fn(&mut Cpu), opcode: u8) -> Result<u8, String> {
+    pub fn implied(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::Implied);
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             inst(cpu);
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_jsr(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_jsr(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteJSR);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_stack(cpu.sp);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
@@ -217,13 +93,12 @@ impl Addressing {
         if sub_tick == 6 {
             self.high = self.read_operand(memory, cpu);
             cpu.set_pc(self.low, self.high);
-            cpu.inst = "JSR";
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    fn absolute_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), index: u8, opcode: u8) -> Result<u8, String> {
+    fn absolute_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), index: u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
@@ -234,13 +109,16 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
-            return if self.fix_high {
-                self.high += 1;
-                Ok(sub_tick + 1)
-            } else {
-                inst(cpu, memory.get_from_low_high(self.low, self.high));
-                Ok(1)
-            };
+            let value = memory.get_from_low_high(self.low, self.high);
+            // without a page-cross penalty the fix-up cycle is always spent
+            if !self.fix_high && OPCODES[opcode as usize].page_cross {
+                inst(cpu, value);
+                return Ok(1);
+            }
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
         }
         if sub_tick == 5 {
             inst(cpu, memory.get_from_low_high(self.low, self.high));
@@ -249,46 +127,52 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_indexed_read_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn absolute_indexed_read_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteIndexedReadX);
+            self.clear_operands();
         }
         self.absolute_indexed_read(sub_tick, cpu, memory, inst, cpu.x, opcode)
     }
 
-    pub fn absolute_indexed_read_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn absolute_indexed_read_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteIndexedReadY);
+            self.clear_operands();
         }
         self.absolute_indexed_read(sub_tick, cpu, memory, inst, cpu.y, opcode)
     }
 
-    pub fn relative(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> bool, opcode: u8) -> Result<u8, String> {
+    pub fn relative(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> bool, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::Relative);
+            self.clear_operands();
             self.latch = self.read_operand(memory, cpu);
             return if inst(cpu) {
+                self.interrupt_poll = cpu.interrupt_poll;
                 Ok(sub_tick + 1)
             } else {
                 let (r, fix) = cpu.get_pcl().overflowing_add(self.latch);
-                self.high = cpu.get_pch().checked_add_signed(self.relative_fix_high(fix, cpu.get_pcl())).unwrap();
+                self.high = cpu.get_pch().wrapping_add_signed(self.relative_fix_high(fix, cpu.get_pcl()));
                 self.low = r;
                 Ok(1)
             };
         }
         if sub_tick == 3 {
+            memory.get_from_word(cpu.pc);
             let (r, fix) = cpu.get_pcl().overflowing_add(self.latch);
             let fix_high = self.relative_fix_high(fix, cpu.get_pcl());
-            self.high = cpu.get_pch().checked_add_signed(fix_high).unwrap();
+            self.high = cpu.get_pch().wrapping_add_signed(fix_high);
             self.low = r;
             cpu.set_pcl(self.low);
             return if fix_high != 0 {
                 Ok(sub_tick + 1)
             } else {
+                // a taken branch that stays on the page does not poll after the operand fetch, an
+                // interrupt arriving now waits until the end of the next instruction
+                cpu.interrupt_poll = self.interrupt_poll;
                 Ok(1)
             };
         }
         if sub_tick == 4 {
+            memory.get_from_word(cpu.pc);
             cpu.set_pch(self.high);
             return Ok(1);
         }
@@ -304,12 +188,14 @@ impl Addressing {
         }
     }
 
-    pub fn implied_rts(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &Memory, opcode: u8) -> Result<u8, String> {
+    pub fn implied_rts(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ImpliedRTS);
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_stack(cpu.sp);
             cpu.sp = cpu.sp.wrapping_add(1);
             return Ok(sub_tick + 1);
         }
@@ -323,24 +209,25 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 6 {
-            cpu.get_and_increment_pc();
-            cpu.inst = "RTS";
+            memory.get_from_word(cpu.get_and_increment_pc());
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn implied_rti(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &Memory, opcode: u8) -> Result<u8, String> {
+    pub fn implied_rti(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ImpliedRTI);
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_stack(cpu.sp);
             cpu.sp = cpu.sp.wrapping_add(1);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
-            cpu.p = memory.get_stack(cpu.sp);
+            cpu.set_status_from_stack(memory.get_stack(cpu.sp));
             cpu.sp = cpu.sp.wrapping_add(1);
             return Ok(sub_tick + 1);
         }
@@ -351,15 +238,14 @@ impl Addressing {
         }
         if sub_tick == 6 {
             cpu.set_pch(memory.get_stack(cpu.sp));
-            cpu.inst = "RTI";
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteWrite);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -374,9 +260,9 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn zero_page_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn zero_page_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageWrite);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -387,9 +273,9 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn absolute_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteRead);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -404,9 +290,9 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteReadModifyWrite);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -415,35 +301,36 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
+            self.latch = memory.get_from_low_high(self.low, self.high);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 5 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            self.latch = inst(cpu, self.latch);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 6 {
-            let value = inst(cpu, memory.get_from_low_high(self.low, self.high));
-            memory.set_from_low_high(self.low, self.high, value);
+            memory.set_from_low_high(self.low, self.high, self.latch);
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_jmp(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_jmp(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteJMP);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
             self.high = self.read_operand(memory, cpu);
             cpu.set_pc(self.low, self.high);
-            cpu.inst = "JMP";
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    fn absolute_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
+    fn absolute_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
@@ -454,8 +341,9 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
+            memory.get_from_low_high(self.low, self.high);
             if self.fix_high {
-                self.high += 1;
+                self.high = self.high.wrapping_add(1);
             }
             return Ok(sub_tick + 1);
         }
@@ -466,23 +354,72 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn absolute_indexed_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_indexed_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteIndexedWriteX);
+            self.clear_operands();
         }
         self.absolute_indexed_write(sub_tick, cpu, memory, inst, cpu.x, opcode)
     }
 
-    pub fn absolute_indexed_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn absolute_indexed_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteIndexedWriteY);
+            self.clear_operands();
         }
         self.absolute_indexed_write(sub_tick, cpu, memory, inst, cpu.y, opcode)
     }
 
-    pub fn zero_page_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+    /// SHA/SHX/SHY/TAS: the stored value is ANDed with the high byte of the base address plus one,
+    /// and when the index crosses a page that value also replaces the high byte of the target.
+    fn absolute_indexed_write_unstable(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.low = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            (self.low, self.fix_high) = self.low.overflowing_add(index);
+            self.high = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            memory.get_from_low_high(self.low, self.high);
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            self.write_unstable(cpu, memory, inst);
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    fn write_unstable(&mut self, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8) {
+        let high_plus_one = if self.fix_high { self.high } else { self.high.wrapping_add(1) };
+        let value = inst(cpu, high_plus_one);
+        if self.fix_high {
+            self.high = value;
+        }
+        memory.set_from_low_high(self.low, self.high, value);
+    }
+
+    pub fn absolute_indexed_write_unstable_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageReadModifyWrite);
+            self.clear_operands();
+        }
+        self.absolute_indexed_write_unstable(sub_tick, cpu, memory, inst, cpu.x, opcode)
+    }
+
+    pub fn absolute_indexed_write_unstable_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+        }
+        self.absolute_indexed_write_unstable(sub_tick, cpu, memory, inst, cpu.y, opcode)
+    }
+
+    pub fn zero_page_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -491,6 +428,7 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
+            memory.set_from_low(self.low, self.latch);
             self.latch = inst(cpu, self.latch);
             return Ok(sub_tick + 1);
         }
@@ -501,9 +439,9 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn indirect_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn indirect_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::IndirectIndexedRead);
+            self.clear_operands();
             self.latch = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -517,13 +455,16 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 5 {
-            return if self.fix_high {
-                self.high += 1;
-                Ok(sub_tick + 1)
-            } else {
-                inst(cpu, memory.get_from_low_high(self.low, self.high));
-                Ok(1)
-            };
+            let value = memory.get_from_low_high(self.low, self.high);
+            // without a page-cross penalty the fix-up cycle is always spent
+            if !self.fix_high && OPCODES[opcode as usize].page_cross {
+                inst(cpu, value);
+                return Ok(1);
+            }
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
         }
         if sub_tick == 6 {
             inst(cpu, memory.get_from_low_high(self.low, self.high));
@@ -532,9 +473,9 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn indirect_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn indirect_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::IndirectIndexedWrite);
+            self.clear_operands();
             self.latch = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -548,8 +489,9 @@ impl Addressing {
             return Ok(sub_tick + 1);
         }
         if sub_tick == 5 {
+            memory.get_from_low_high(self.low, self.high);
             if self.fix_high {
-                self.high += 1;
+                self.high = self.high.wrapping_add(1);
             }
             return Ok(sub_tick + 1);
         }
@@ -560,18 +502,86 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn accumulator(&mut self, sub_tick: u8, cpu: &mut Cpu, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn indirect_indexed_write_unstable(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            self.latch = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            self.low = memory.get_from_low(self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            self.high = memory.get_from_low(self.latch.wrapping_add(1));
+            (self.low, self.fix_high) = self.low.overflowing_add(cpu.y);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            memory.get_from_low_high(self.low, self.high);
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            self.write_unstable(cpu, memory, inst);
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn indirect_indexed_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::Accumulator);
+            self.clear_operands();
+            self.latch = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            self.low = memory.get_from_low(self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            self.high = memory.get_from_low(self.latch.wrapping_add(1));
+            (self.low, self.fix_high) = self.low.overflowing_add(cpu.y);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            memory.get_from_low_high(self.low, self.high);
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            self.latch = memory.get_from_low_high(self.low, self.high);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 7 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            self.latch = inst(cpu, self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 8 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn accumulator(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             cpu.a = inst(cpu, cpu.a);
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn zero_page_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn zero_page_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageRead);
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -582,12 +592,13 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    fn zero_page_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
+    fn zero_page_indexed_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_from_low(self.low);
             self.low = self.low.wrapping_add(index);
             return Ok(sub_tick + 1);
         }
@@ -598,26 +609,27 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn zero_page_indexed_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn zero_page_indexed_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageIndexedWriteX);
+            self.clear_operands();
         }
         self.zero_page_indexed_write(sub_tick, cpu, memory, inst, cpu.x, opcode)
     }
 
-    // pub fn zero_page_indexed_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
-    //     if sub_tick == 2 {
-    //         self.set_addressing_type(AddressingType::ZeroPageIndexedWriteY);
-    //     }
-    //     self.zero_page_indexed_write(sub_tick, cpu, memory, inst, cpu.y, opcode)
-    // }
+    pub fn zero_page_indexed_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+        }
+        self.zero_page_indexed_write(sub_tick, cpu, memory, inst, cpu.y, opcode)
+    }
 
-    fn zero_page_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), index: u8, opcode: u8) -> Result<u8, String> {
+    fn zero_page_indexed_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), index: u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_from_low(self.low);
             self.low = self.low.wrapping_add(index);
             return Ok(sub_tick + 1);
         }
@@ -628,60 +640,190 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn zero_page_indexed_read_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn zero_page_indexed_read_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageIndexedReadX);
+            self.clear_operands();
         }
         self.zero_page_indexed_read(sub_tick, cpu, memory, inst, cpu.x, opcode)
     }
 
-    // pub fn zero_page_indexed_read_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
-    //     if sub_tick == 2 {
-    //         self.set_addressing_type(AddressingType::ZeroPageIndexedReadY);
-    //     }
-    //     self.zero_page_indexed_read(sub_tick, cpu, memory, inst, cpu.y, opcode)
-    // }
+    pub fn zero_page_indexed_read_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+        }
+        self.zero_page_indexed_read(sub_tick, cpu, memory, inst, cpu.y, opcode)
+    }
 
-    fn zero_page_indexed_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
+    fn zero_page_indexed_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_from_low(self.low);
             self.low = self.low.wrapping_add(index);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 4 {
+            self.latch = memory.get_from_low(self.low);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 5 {
+            memory.set_from_low(self.low, self.latch);
+            self.latch = inst(cpu, self.latch);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 6 {
-            let value = inst(cpu, memory.get_from_low(self.low));
-            memory.set_from_low(self.low, value);
+            memory.set_from_low(self.low, self.latch);
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn zero_page_indexed_read_modify_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn zero_page_indexed_read_modify_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ZeroPageIndexedReadModifyWriteX);
+            self.clear_operands();
         }
         self.zero_page_indexed_read_modify_write(sub_tick, cpu, memory, inst, cpu.x, opcode)
     }
 
-    // pub fn zero_page_indexed_read_modify_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu, u8)->u8, opcode: u8) -> Result<u8, String> {
-    //     if sub_tick == 2 {
-    //         self.set_addressing_type(AddressingType::ZeroPageIndexedReadModifyWriteY);
-    //     }
-    //     self.zero_page_indexed_read_modify_write(sub_tick, cpu, memory, inst, cpu.y, opcode)
-    // }
+    fn absolute_indexed_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, index: u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.low = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            (self.low, self.fix_high) = self.low.overflowing_add(index);
+            self.high = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            memory.get_from_low_high(self.low, self.high);
+            if self.fix_high {
+                self.high = self.high.wrapping_add(1);
+            }
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            self.latch = memory.get_from_low_high(self.low, self.high);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            self.latch = inst(cpu, self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 7 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn absolute_indexed_read_modify_write_x(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+        }
+        self.absolute_indexed_read_modify_write(sub_tick, cpu, memory, inst, cpu.x, opcode)
+    }
+
+    pub fn absolute_indexed_read_modify_write_y(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+        }
+        self.absolute_indexed_read_modify_write(sub_tick, cpu, memory, inst, cpu.y, opcode)
+    }
+
+    pub fn indexed_indirect_read(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            self.latch = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            memory.get_from_low(self.latch);
+            self.latch = self.latch.wrapping_add(cpu.x);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            self.low = memory.get_from_low(self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            self.high = memory.get_from_low(self.latch.wrapping_add(1));
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            inst(cpu, memory.get_from_low_high(self.low, self.high));
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn indexed_indirect_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            self.latch = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            memory.get_from_low(self.latch);
+            self.latch = self.latch.wrapping_add(cpu.x);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            self.low = memory.get_from_low(self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            self.high = memory.get_from_low(self.latch.wrapping_add(1));
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            memory.set_from_low_high(self.low, self.high, inst(cpu));
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
 
-    pub fn absolute_indirect_jmp(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, opcode: u8) -> Result<u8, String> {
+    pub fn indexed_indirect_read_modify_write(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::AbsoluteIndirectJMP);
+            self.clear_operands();
+            self.latch = self.read_operand(memory, cpu);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 3 {
+            memory.get_from_low(self.latch);
+            self.latch = self.latch.wrapping_add(cpu.x);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 4 {
+            self.low = memory.get_from_low(self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 5 {
+            self.high = memory.get_from_low(self.latch.wrapping_add(1));
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 6 {
+            self.latch = memory.get_from_low_high(self.low, self.high);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 7 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            self.latch = inst(cpu, self.latch);
+            return Ok(sub_tick + 1);
+        }
+        if sub_tick == 8 {
+            memory.set_from_low_high(self.low, self.high, self.latch);
+            return Ok(1);
+        }
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn absolute_indirect_jmp(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
             self.low = self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
@@ -696,15 +838,15 @@ impl Addressing {
         }
         if sub_tick == 5 {
             cpu.set_pc(self.latch, memory.get_from_low_high(self.low.wrapping_add(1), self.high));
-            cpu.inst = "JMP";
             return Ok(1);
         }
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn implied_php_pha(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
+    pub fn implied_php_pha(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu) -> u8, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ImpliedPHA);
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
@@ -715,12 +857,14 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn implied_plp_pla(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &Memory, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
+    pub fn implied_plp_pla(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, inst: fn(&mut Cpu, u8), opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ImpliedPLA);
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
             return Ok(sub_tick + 1);
         }
         if sub_tick == 3 {
+            memory.get_stack(cpu.sp);
             cpu.sp = cpu.sp.wrapping_add(1);
             return Ok(sub_tick + 1);
         }
@@ -731,36 +875,227 @@ impl Addressing {
         Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
     }
 
-    pub fn implied_irq(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut Memory) -> Result<u8, String> {
+    pub fn implied_brk(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
         if sub_tick == 2 {
-            self.set_addressing_type(AddressingType::ImpliedIRQ);
+            self.clear_operands();
+            self.read_operand(memory, cpu);
             return Ok(sub_tick + 1);
         }
+        self.interrupt_sequence(sub_tick, cpu, memory, true)
+            .ok_or_else(|| format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+
+    pub fn implied_irq(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
+            return Ok(sub_tick + 1);
+        }
+        self.interrupt_sequence(sub_tick, cpu, memory, false)
+            .ok_or_else(|| format!("Illegal sub_tick {} for opcode IRQ", sub_tick))
+    }
+
+    /// Cycles 3-7 shared by BRK, IRQ and NMI. Only BRK pushes P with the B flag set, a hardware
+    /// interrupt pushes it cleared, which is the only way a handler can tell them apart.
+    fn interrupt_sequence(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, brk: bool) -> Option<u8> {
         if sub_tick == 3 {
             memory.set_stack(cpu.sp, cpu.get_pch());
             cpu.sp = cpu.sp.wrapping_sub(1);
-            return Ok(sub_tick + 1);
+            return Some(sub_tick + 1);
         }
         if sub_tick == 4 {
             memory.set_stack(cpu.sp, cpu.get_pcl());
             cpu.sp = cpu.sp.wrapping_sub(1);
-            return Ok(sub_tick + 1);
+            return Some(sub_tick + 1);
         }
         if sub_tick == 5 {
-            memory.set_stack(cpu.sp, cpu.p);
+            let p = if brk { cpu.p | 0x30 } else { (cpu.p & !0x10) | 0x20 };
+            memory.set_stack(cpu.sp, p);
             cpu.sp = cpu.sp.wrapping_sub(1);
-            return Ok(sub_tick + 1);
+            self.vector = Self::interrupt_vector(cpu);
+            return Some(sub_tick + 1);
         }
         if sub_tick == 6 {
-            cpu.set_pcl(memory.get_from_word(0xFFFE));
-            return Ok(sub_tick + 1);
+            cpu.set_pcl(memory.get_from_word(self.vector));
+            cpu.set_interrupt_flag(true);
+            return Some(sub_tick + 1);
         }
         if sub_tick == 7 {
-            cpu.set_pch(memory.get_from_word(0xFFFF));
-            cpu.inst = "IRQ";
-            cpu.interrupted = false;
+            cpu.set_pch(memory.get_from_word(self.vector + 1));
+            cpu.inst = match (brk, self.vector) {
+                (true, _) => "BRK",
+                (false, 0xFFFA) => "NMI",
+                (false, _) => "IRQ",
+            };
+            // the first instruction of the handler always runs before the next interrupt
+            cpu.interrupt_poll = false;
+            return Some(1);
+        }
+        None
+    }
+
+    /// The vector is picked while P is pushed, so an NMI that arrives until then hijacks a running
+    /// IRQ or BRK sequence. A hijacked BRK still pushes B set, but continues at the NMI vector.
+    fn interrupt_vector(cpu: &mut Cpu) -> u16 {
+        if cpu.nmi_pending {
+            cpu.nmi_pending = false;
+            0xFFFA
+        } else {
+            0xFFFE
+        }
+    }
+
+    pub fn implied_jam(&mut self, sub_tick: u8, cpu: &mut Cpu, memory: &mut impl Bus, opcode: u8) -> Result<u8, String> {
+        if sub_tick == 2 {
+            self.clear_operands();
+            memory.get_from_word(cpu.pc);
+            cpu.jammed = true;
             return Ok(1);
         }
-        Err(format!("Illegal sub_tick {} for opcode IRQ", sub_tick))
+        Err(format!("Illegal sub_tick {} for opcode {:02X}", sub_tick, opcode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::addressing::Addressing;
+    use crate::emulator::cpu::Cpu;
+    use crate::emulator::cpu_tests::{Access, FlatRam};
+    use crate::emulator::cpu_tests::Access::*;
+    use crate::emulator::logger::CpuLogger;
+
+    /// Runs the instruction `program` starts with at $0200 on zero filled RAM and returns its bus
+    /// accesses, the opcode fetch included.
+    fn run(cpu: &mut Cpu, ram: &mut FlatRam, program: &[u8]) -> Vec<Access> {
+        ram.data[0x0200..0x0200 + program.len()].copy_from_slice(program);
+        ram.accesses = Some(Vec::new());
+        cpu.pc = 0x0200;
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        loop {
+            cpu.tick(&mut logger, ram, &mut addressing).unwrap();
+            if cpu.state == 1 {
+                return ram.accesses.take().unwrap();
+            }
+        }
+    }
+
+    fn zeroed_ram() -> FlatRam {
+        FlatRam::new(vec![0; 0x10000])
+    }
+
+    #[test]
+    fn indexed_indirect_wraps_in_zero_page() {
+        // LDA ($FE,X) with X = 1 takes the pointer from $FF and $00
+        let mut cpu = Cpu::new();
+        cpu.x = 1;
+        let mut ram = zeroed_ram();
+        ram.data[0x00FF] = 0x34;
+        ram.data[0x0000] = 0x12;
+        ram.data[0x1234] = 0x99;
+        let accesses = run(&mut cpu, &mut ram, &[0xA1, 0xFE]);
+        assert_eq!(accesses, [Read(0x0200, 0xA1), Read(0x0201, 0xFE), Read(0x00FE, 0), Read(0x00FF, 0x34), Read(0x0000, 0x12), Read(0x1234, 0x99)]);
+        assert_eq!(cpu.a, 0x99);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn zero_page_y_wraps_in_zero_page() {
+        // LDX $F0,Y and STX $F0,Y with Y = $20 use $10, after a dummy read of $F0
+        let mut cpu = Cpu::new();
+        cpu.y = 0x20;
+        let mut ram = zeroed_ram();
+        ram.data[0x0010] = 0x77;
+        let accesses = run(&mut cpu, &mut ram, &[0xB6, 0xF0]);
+        assert_eq!(accesses, [Read(0x0200, 0xB6), Read(0x0201, 0xF0), Read(0x00F0, 0), Read(0x0010, 0x77)]);
+        assert_eq!(cpu.x, 0x77);
+
+        let mut ram = zeroed_ram();
+        let accesses = run(&mut cpu, &mut ram, &[0x96, 0xF0]);
+        assert_eq!(accesses, [Read(0x0200, 0x96), Read(0x0201, 0xF0), Read(0x00F0, 0), Write(0x0010, 0x77)]);
+    }
+
+    #[test]
+    fn unstable_stores_and_h_plus_one() {
+        // SHY $1234,X stores Y & ($12 + 1)
+        let mut cpu = Cpu::new();
+        cpu.x = 1;
+        cpu.y = 0xFF;
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0x9C, 0x34, 0x12]);
+        assert_eq!(accesses, [Read(0x0200, 0x9C), Read(0x0201, 0x34), Read(0x0202, 0x12), Read(0x1235, 0), Write(0x1235, 0x13)]);
+
+        // crossing the page the stored value replaces the high byte of the address
+        cpu.y = 0x0F;
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0x9C, 0xFF, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1200, 0), Write(0x0300, 0x03)]);
+
+        // SHX $12FF,Y
+        cpu.x = 0x21;
+        cpu.y = 1;
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0x9E, 0xFF, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1200, 0), Write(0x0100, 0x01)]);
+
+        // TAS $1234,Y sets SP to A & X and stores SP & ($12 + 1)
+        cpu.a = 0xF0;
+        cpu.x = 0x3C;
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0x9B, 0x34, 0x12]);
+        assert_eq!(accesses[4], Write(0x1235, 0x10));
+        assert_eq!(cpu.sp, 0x30);
+
+        // SHA ($10),Y
+        cpu.a = 0xFF;
+        cpu.x = 0xFF;
+        let mut ram = zeroed_ram();
+        ram.data[0x0010] = 0x34;
+        ram.data[0x0011] = 0x12;
+        let accesses = run(&mut cpu, &mut ram, &[0x93, 0x10]);
+        assert_eq!(accesses[2..], [Read(0x0010, 0x34), Read(0x0011, 0x12), Read(0x1235, 0), Write(0x1235, 0x13)]);
+    }
+
+    #[test]
+    fn jam_halts_the_cpu() {
+        let mut cpu = Cpu::new();
+        let mut ram = zeroed_ram();
+        run(&mut cpu, &mut ram, &[0x02]);
+        assert!(cpu.jammed);
+        let pc = cpu.pc;
+        ram.accesses = Some(Vec::new());
+        let mut addressing = Addressing::new();
+        for _ in 0..10 {
+            cpu.tick(&mut CpuLogger::new(), &mut ram, &mut addressing).unwrap();
+        }
+        assert_eq!(ram.accesses, Some(Vec::new()));
+        assert_eq!(cpu.pc, pc);
+    }
+
+    #[test]
+    fn indexed_dummy_reads() {
+        let mut cpu = Cpu::new();
+        cpu.x = 1;
+        // LDA $1234,X reads only the final address, LDA $12FF,X first reads the un-fixed one
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0xBD, 0x34, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1235, 0)]);
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0xBD, 0xFF, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1200, 0), Read(0x1300, 0)]);
+
+        // STA $1234,X reads before writing even without a page crossing
+        cpu.a = 0x42;
+        let accesses = run(&mut cpu, &mut zeroed_ram(), &[0x9D, 0x34, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1235, 0), Write(0x1235, 0x42)]);
+    }
+
+    #[test]
+    fn read_modify_write_writes_twice() {
+        // INC $1234
+        let mut cpu = Cpu::new();
+        let mut ram = zeroed_ram();
+        ram.data[0x1234] = 5;
+        let accesses = run(&mut cpu, &mut ram, &[0xEE, 0x34, 0x12]);
+        assert_eq!(accesses, [Read(0x0200, 0xEE), Read(0x0201, 0x34), Read(0x0202, 0x12), Read(0x1234, 5), Write(0x1234, 5), Write(0x1234, 6)]);
+
+        // INC $1234,X
+        cpu.x = 1;
+        ram.data[0x1235] = 7;
+        let accesses = run(&mut cpu, &mut ram, &[0xFE, 0x34, 0x12]);
+        assert_eq!(accesses[3..], [Read(0x1235, 7), Read(0x1235, 7), Write(0x1235, 7), Write(0x1235, 8)]);
+    }
+}
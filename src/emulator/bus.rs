@@ -0,0 +1,45 @@
+/// Everything `Cpu` and `Addressing` see of the machine they are plugged into. `Memory` is the
+/// C64 implementation, anything with 64K of address space can provide its own.
+pub trait Bus {
+    fn read(&mut self, address: u16) -> u8;
+
+    fn write(&mut self, address: u16, value: u8);
+
+    /// Called by `Cpu::tick` after every clock cycle, also while the CPU is jammed, so the
+    /// devices on the bus can run in lockstep with it.
+    fn cycle(&mut self) {}
+
+    /// True while a device holds the DMA line and masters the bus, the CPU is halted until it
+    /// lets go.
+    fn dma(&self) -> bool {
+        false
+    }
+
+    fn get_from_word(&mut self, loc: u16) -> u8 {
+        self.read(loc)
+    }
+
+    fn get_from_low(&mut self, low: u8) -> u8 {
+        self.read(low as u16)
+    }
+
+    fn get_from_low_high(&mut self, low: u8, high: u8) -> u8 {
+        self.read(((low as u16) & 0x00FF) | (((high as u16) << 8) & 0xFF00))
+    }
+
+    fn set_from_low(&mut self, low: u8, value: u8) {
+        self.write(low as u16, value);
+    }
+
+    fn set_from_low_high(&mut self, low: u8, high: u8, value: u8) {
+        self.write(((low as u16) & 0x00FF) | (((high as u16) << 8) & 0xFF00), value);
+    }
+
+    fn get_stack(&mut self, sp: u8) -> u8 {
+        self.read(sp as u16 | 0x0100)
+    }
+
+    fn set_stack(&mut self, sp: u8, value: u8) {
+        self.write(sp as u16 | 0x0100, value);
+    }
+}
\ No newline at end of file
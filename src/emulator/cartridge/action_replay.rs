@@ -0,0 +1,110 @@
+use crate::emulator::cartridge::{BANK_SIZE, Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+const GAME_LOW: u8 = 0x01;
+const EXROM_HIGH: u8 = 0x02;
+const DISABLE: u8 = 0x04;
+const BANK: u8 = 0x18;
+const RAM_ENABLE: u8 = 0x20;
+const RELEASE_FREEZE: u8 = 0x40;
+
+/// Action Replay 4 to 6: four 8K ROM banks and 8K RAM, configured by writing to $DE00. I/O2
+/// mirrors the last page of ROML. The freeze button asserts NMI and switches to Ultimax mode
+/// until the freeze is released through $DE00.
+pub struct ActionReplay {
+    rom: Banks,
+    ram: Vec<u8>,
+    control: u8,
+    frozen: bool,
+    /// Switched off through $DE00, only a reset or the freeze button bring it back.
+    disabled: bool,
+}
+
+impl ActionReplay {
+    pub fn new(crt: &Crt) -> Result<ActionReplay, String> {
+        Ok(ActionReplay {
+            rom: Banks::from_crt(crt, &[0x8000])?,
+            ram: vec![0; BANK_SIZE],
+            control: 0,
+            frozen: false,
+            disabled: false,
+        })
+    }
+
+    fn bank(&self) -> usize {
+        ((self.control & BANK) >> 3) as usize
+    }
+
+    fn ram_enabled(&self) -> bool {
+        self.control & RAM_ENABLE != 0
+    }
+}
+
+impl Mapper for ActionReplay {
+    fn exrom(&self) -> bool {
+        self.disabled || self.frozen || self.control & EXROM_HIGH != 0
+    }
+
+    fn game(&self) -> bool {
+        self.disabled || (!self.frozen && self.control & GAME_LOW == 0)
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        if self.ram_enabled() {
+            Some(self.ram[offset])
+        } else {
+            Some(self.rom.read(self.bank(), offset))
+        }
+    }
+
+    fn read_romh(&mut self, offset: usize) -> Option<u8> {
+        self.peek_romh(offset)
+    }
+
+    fn peek_romh(&self, offset: usize) -> Option<u8> {
+        Some(self.rom.read(self.bank(), offset))
+    }
+
+    fn write_roml(&mut self, offset: usize, value: u8) {
+        if self.ram_enabled() {
+            self.ram[offset] = value;
+        }
+    }
+
+    fn write_io1(&mut self, _loc: usize, value: u8) {
+        if self.disabled {
+            return;
+        }
+        self.control = value;
+        if value & RELEASE_FREEZE != 0 {
+            self.frozen = false;
+        }
+        if value & DISABLE != 0 {
+            self.disabled = true;
+        }
+    }
+
+    fn read_io2(&mut self, loc: usize) -> Option<u8> {
+        if self.disabled {
+            None
+        } else {
+            self.read_roml(0x1F00 | (loc & 0xFF))
+        }
+    }
+
+    fn write_io2(&mut self, loc: usize, value: u8) {
+        if !self.disabled {
+            self.write_roml(0x1F00 | (loc & 0xFF), value);
+        }
+    }
+
+    fn nmi(&self) -> bool {
+        self.frozen
+    }
+
+    fn freeze(&mut self) {
+        self.control = 0;
+        self.frozen = true;
+        self.disabled = false;
+    }
+}
@@ -0,0 +1,41 @@
+use crate::emulator::cartridge::{Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// C64 Game System and System 3: 8K banks in ROML. A write to $DE00+n selects bank n, whatever
+/// the value, and any read from I/O1 selects bank 0.
+pub struct C64Gs {
+    banks: Banks,
+    bank: usize,
+}
+
+impl C64Gs {
+    pub fn new(crt: &Crt) -> Result<C64Gs, String> {
+        Ok(C64Gs {
+            banks: Banks::from_crt(crt, &[0x8000])?,
+            bank: 0,
+        })
+    }
+}
+
+impl Mapper for C64Gs {
+    fn exrom(&self) -> bool {
+        false
+    }
+
+    fn game(&self) -> bool {
+        true
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        Some(self.banks.read(self.bank, offset))
+    }
+
+    fn read_io1(&mut self, _loc: usize) -> Option<u8> {
+        self.bank = 0;
+        None
+    }
+
+    fn write_io1(&mut self, loc: usize, _value: u8) {
+        self.bank = loc & 0x3F;
+    }
+}
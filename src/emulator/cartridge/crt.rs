@@ -0,0 +1,140 @@
+use std::fs;
+
+const SIGNATURE: &[u8] = b"C64 CARTRIDGE   ";
+const CHIP_SIGNATURE: &[u8] = b"CHIP";
+const CHIP_HEADER_LENGTH: usize = 0x10;
+
+/// One CHIP packet: a ROM, RAM or flash image for one bank.
+pub struct Chip {
+    pub chip_type: u16,
+    pub bank: u16,
+    pub load_address: u16,
+    pub data: Vec<u8>,
+}
+
+/// A cartridge image in the CCS64 CRT format, all numbers are big endian.
+pub struct Crt {
+    pub name: String,
+    pub hardware_type: u16,
+    /// Initial levels of the EXROM and GAME lines, true is high (inactive).
+    pub exrom: bool,
+    pub game: bool,
+    pub chips: Vec<Chip>,
+}
+
+impl Crt {
+    pub fn load(path: &str) -> Result<Crt, String> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Error loading cartridge '{}': {}", path, e))?;
+        Crt::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Crt, String> {
+        if data.len() < 0x40 || &data[0..0x10] != SIGNATURE {
+            return Err(String::from("Not a CRT file"));
+        }
+        let header_length = (be32(data, 0x10) as usize).max(0x40);
+        let name_bytes = &data[0x20..0x40];
+        let name_length = name_bytes.iter().position(|b| *b == 0).unwrap_or(name_bytes.len());
+        let mut crt = Crt {
+            name: String::from_utf8_lossy(&name_bytes[..name_length]).to_string(),
+            hardware_type: be16(data, 0x16),
+            exrom: data[0x18] != 0,
+            game: data[0x19] != 0,
+            chips: Vec::new(),
+        };
+
+        let mut offset = header_length;
+        while offset + CHIP_HEADER_LENGTH <= data.len() {
+            if &data[offset..offset + 4] != CHIP_SIGNATURE {
+                return Err(format!("Missing CHIP packet at offset {:X}", offset));
+            }
+            let packet_length = be32(data, offset + 4) as usize;
+            let size = be16(data, offset + 0x0E) as usize;
+            let start = offset + CHIP_HEADER_LENGTH;
+            if start + size > data.len() || packet_length < CHIP_HEADER_LENGTH {
+                return Err(format!("Truncated CHIP packet at offset {:X}", offset));
+            }
+            crt.chips.push(Chip {
+                chip_type: be16(data, offset + 0x08),
+                bank: be16(data, offset + 0x0A),
+                load_address: be16(data, offset + 0x0C),
+                data: data[start..start + size].to_vec(),
+            });
+            offset += packet_length.max(CHIP_HEADER_LENGTH + size);
+        }
+        Ok(crt)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_bytes())
+            .map_err(|e| format!("Error saving cartridge '{}': {}", path, e))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = SIGNATURE.to_vec();
+        data.extend_from_slice(&0x40u32.to_be_bytes());
+        // version 1.0
+        data.extend_from_slice(&0x0100u16.to_be_bytes());
+        data.extend_from_slice(&self.hardware_type.to_be_bytes());
+        data.extend_from_slice(&[self.exrom as u8, self.game as u8, 0, 0, 0, 0, 0, 0]);
+        let mut name = self.name.as_bytes().to_vec();
+        name.resize(0x20, 0);
+        data.extend_from_slice(&name);
+        for chip in &self.chips {
+            data.extend_from_slice(CHIP_SIGNATURE);
+            data.extend_from_slice(&((CHIP_HEADER_LENGTH + chip.data.len()) as u32).to_be_bytes());
+            data.extend_from_slice(&chip.chip_type.to_be_bytes());
+            data.extend_from_slice(&chip.bank.to_be_bytes());
+            data.extend_from_slice(&chip.load_address.to_be_bytes());
+            data.extend_from_slice(&(chip.data.len() as u16).to_be_bytes());
+            data.extend_from_slice(&chip.data);
+        }
+        data
+    }
+}
+
+fn be16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /// Builds a CRT image, `chips` are (bank, load address, data).
+    pub fn crt_image(hardware_type: u16, exrom: u8, game: u8, chips: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        Crt {
+            name: String::from("TEST"),
+            hardware_type,
+            exrom: exrom != 0,
+            game: game != 0,
+            chips: chips.iter().map(|(bank, load_address, data)| Chip {
+                chip_type: 0,
+                bank: *bank,
+                load_address: *load_address,
+                data: data.clone(),
+            }).collect(),
+        }.to_bytes()
+    }
+
+    #[test]
+    fn parse() {
+        let image = crt_image(0, 0, 1, &[(0, 0x8000, vec![0xAA; 0x2000])]);
+        let crt = Crt::parse(&image).unwrap();
+        assert_eq!(crt.name, "TEST");
+        assert_eq!(crt.hardware_type, 0);
+        assert!(!crt.exrom);
+        assert!(crt.game);
+        assert_eq!(crt.chips.len(), 1);
+        assert_eq!(crt.chips[0].load_address, 0x8000);
+        assert_eq!(crt.chips[0].data.len(), 0x2000);
+
+        assert!(Crt::parse(&image[..0x30]).is_err());
+        assert!(Crt::parse(&image[..0x60]).is_err());
+    }
+}
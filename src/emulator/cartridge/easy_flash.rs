@@ -0,0 +1,220 @@
+use crate::emulator::cartridge::{BANK_SIZE, CHIP_FLASH, CHIP_RAM, Mapper};
+use crate::emulator::cartridge::crt::{Chip, Crt};
+
+const BANKS: usize = 64;
+const FLASH_SIZE: usize = BANKS * BANK_SIZE;
+const SECTOR_SIZE: usize = 0x10000;
+
+const MANUFACTURER_AMD: u8 = 0x01;
+const DEVICE_AM29F040: u8 = 0xA4;
+
+const GAME_LOW: u8 = 0x01;
+const EXROM_LOW: u8 = 0x02;
+/// GAME follows `GAME_LOW` instead of the boot jumper.
+const GAME_MODE: u8 = 0x04;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum FlashState {
+    Read,
+    Unlock1,
+    Unlock2,
+    Program,
+    Erase,
+    EraseUnlock1,
+    EraseUnlock2,
+    AutoSelect,
+}
+
+/// One AM29F040 chip. Commands are unlocked by writing $AA to $555 and $55 to $2AA, only the lower
+/// 11 address bits are decoded. Programming and erasing finish immediately.
+struct Flash {
+    data: Vec<u8>,
+    state: FlashState,
+    modified: bool,
+}
+
+impl Flash {
+    fn new() -> Flash {
+        Flash {
+            data: vec![0xFF; FLASH_SIZE],
+            state: FlashState::Read,
+            modified: false,
+        }
+    }
+
+    fn read(&self, address: usize) -> u8 {
+        if self.state == FlashState::AutoSelect {
+            match address & 0xFF {
+                0x00 => return MANUFACTURER_AMD,
+                0x01 => return DEVICE_AM29F040,
+                // no sector is protected
+                0x02 => return 0x00,
+                _ => {}
+            }
+        }
+        self.data[address]
+    }
+
+    fn write(&mut self, address: usize, value: u8) {
+        let command = address & 0x7FF;
+        self.state = match (self.state, command, value) {
+            (FlashState::Program, _, _) => {
+                // programming can only clear bits
+                self.data[address] &= value;
+                self.modified = true;
+                FlashState::Read
+            }
+            (_, _, 0xF0) => FlashState::Read,
+            (FlashState::Read | FlashState::AutoSelect, 0x555, 0xAA) => FlashState::Unlock1,
+            (FlashState::Unlock1, 0x2AA, 0x55) => FlashState::Unlock2,
+            (FlashState::Unlock2, 0x555, 0xA0) => FlashState::Program,
+            (FlashState::Unlock2, 0x555, 0x80) => FlashState::Erase,
+            (FlashState::Unlock2, 0x555, 0x90) => FlashState::AutoSelect,
+            (FlashState::Erase, 0x555, 0xAA) => FlashState::EraseUnlock1,
+            (FlashState::EraseUnlock1, 0x2AA, 0x55) => FlashState::EraseUnlock2,
+            (FlashState::EraseUnlock2, 0x555, 0x10) => {
+                self.data.fill(0xFF);
+                self.modified = true;
+                FlashState::Read
+            }
+            (FlashState::EraseUnlock2, _, 0x30) => {
+                let sector = address & !(SECTOR_SIZE - 1);
+                self.data[sector..sector + SECTOR_SIZE].fill(0xFF);
+                self.modified = true;
+                FlashState::Read
+            }
+            (FlashState::AutoSelect, _, _) => FlashState::AutoSelect,
+            _ => FlashState::Read,
+        };
+    }
+}
+
+/// EasyFlash: 64 banks of two 8K windows in two flash chips, one for ROML and one for ROMH.
+/// $DE00 selects the bank, $DE02 drives the EXROM and GAME lines, and I/O2 is 256 bytes of RAM.
+/// The flash is written in Ultimax mode, where the ROMH chip shows at $E000.
+pub struct EasyFlash {
+    name: String,
+    flash: [Flash; 2],
+    bank: usize,
+    control: u8,
+    ram: [u8; 0x100],
+}
+
+impl EasyFlash {
+    pub fn new(crt: &Crt) -> Result<EasyFlash, String> {
+        let mut easy_flash = EasyFlash {
+            name: crt.name.clone(),
+            flash: [Flash::new(), Flash::new()],
+            bank: 0,
+            control: 0,
+            ram: [0; 0x100],
+        };
+        for chip in &crt.chips {
+            if chip.chip_type == CHIP_RAM {
+                continue;
+            }
+            let flash = match chip.load_address {
+                0x8000 => 0,
+                0xA000 | 0xE000 => 1,
+                address => return Err(format!("Unsupported CHIP load address {:04X}", address)),
+            };
+            let bank = chip.bank as usize;
+            if bank >= BANKS || chip.data.len() > BANK_SIZE {
+                return Err(format!("Unexpected {} byte CHIP in bank {}", chip.data.len(), bank));
+            }
+            let start = bank * BANK_SIZE;
+            easy_flash.flash[flash].data[start..start + chip.data.len()].copy_from_slice(&chip.data);
+        }
+        Ok(easy_flash)
+    }
+
+    fn address(&self, offset: usize) -> usize {
+        self.bank * BANK_SIZE + offset
+    }
+
+    fn ultimax(&self) -> bool {
+        self.exrom() && !self.game()
+    }
+}
+
+impl Mapper for EasyFlash {
+    fn exrom(&self) -> bool {
+        self.control & EXROM_LOW == 0
+    }
+
+    fn game(&self) -> bool {
+        // the boot jumper pulls GAME low, which starts in Ultimax mode
+        self.control & GAME_MODE != 0 && self.control & GAME_LOW == 0
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        Some(self.flash[0].read(self.address(offset)))
+    }
+
+    fn read_romh(&mut self, offset: usize) -> Option<u8> {
+        self.peek_romh(offset)
+    }
+
+    fn peek_romh(&self, offset: usize) -> Option<u8> {
+        Some(self.flash[1].read(self.address(offset)))
+    }
+
+    fn write_roml(&mut self, offset: usize, value: u8) {
+        if self.ultimax() {
+            let address = self.address(offset);
+            self.flash[0].write(address, value);
+        }
+    }
+
+    fn write_romh(&mut self, offset: usize, value: u8) {
+        if self.ultimax() {
+            let address = self.address(offset);
+            self.flash[1].write(address, value);
+        }
+    }
+
+    fn write_io1(&mut self, loc: usize, value: u8) {
+        if loc & 0x02 == 0 {
+            self.bank = (value & 0x3F) as usize;
+        } else {
+            // bit 7 is the LED
+            self.control = value & 0x87;
+        }
+    }
+
+    fn read_io2(&mut self, loc: usize) -> Option<u8> {
+        Some(self.ram[loc & 0xFF])
+    }
+
+    fn write_io2(&mut self, loc: usize, value: u8) {
+        self.ram[loc & 0xFF] = value;
+    }
+
+    fn modified_image(&self) -> Option<Crt> {
+        if !self.flash.iter().any(|flash| flash.modified) {
+            return None;
+        }
+        let mut crt = Crt {
+            name: self.name.clone(),
+            hardware_type: 32,
+            exrom: true,
+            game: false,
+            chips: Vec::new(),
+        };
+        for bank in 0..BANKS {
+            for (flash, load_address) in self.flash.iter().zip([0x8000, 0xA000]) {
+                let data = &flash.data[bank * BANK_SIZE..(bank + 1) * BANK_SIZE];
+                // erased banks are left out, like the EasyFlash tools do
+                if data.iter().any(|b| *b != 0xFF) {
+                    crt.chips.push(Chip {
+                        chip_type: CHIP_FLASH,
+                        bank: bank as u16,
+                        load_address,
+                        data: data.to_vec(),
+                    });
+                }
+            }
+        }
+        Some(crt)
+    }
+}
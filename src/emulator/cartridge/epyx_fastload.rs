@@ -0,0 +1,50 @@
+use crate::emulator::cartridge::{Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// Cycles until the capacitor has charged and pulls EXROM high.
+const CHARGE_CYCLES: u32 = 512;
+
+/// Epyx FastLoad: 8K ROM in ROML. Reading ROML or I/O1 discharges a capacitor that keeps the
+/// ROM switched on, it switches itself off when left alone for a while. I/O2 always shows the
+/// last page of the ROM.
+pub struct EpyxFastLoad {
+    rom: Banks,
+    cycles: u32,
+}
+
+impl EpyxFastLoad {
+    pub fn new(crt: &Crt) -> Result<EpyxFastLoad, String> {
+        Ok(EpyxFastLoad {
+            rom: Banks::from_crt(crt, &[0x8000])?,
+            cycles: 0,
+        })
+    }
+}
+
+impl Mapper for EpyxFastLoad {
+    fn exrom(&self) -> bool {
+        self.cycles >= CHARGE_CYCLES
+    }
+
+    fn game(&self) -> bool {
+        true
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        self.cycles = 0;
+        Some(self.rom.read(0, offset))
+    }
+
+    fn read_io1(&mut self, _loc: usize) -> Option<u8> {
+        self.cycles = 0;
+        None
+    }
+
+    fn read_io2(&mut self, loc: usize) -> Option<u8> {
+        Some(self.rom.read(0, 0x1F00 | (loc & 0xFF)))
+    }
+
+    fn tick(&mut self) {
+        self.cycles = (self.cycles + 1).min(CHARGE_CYCLES);
+    }
+}
@@ -0,0 +1,42 @@
+use crate::emulator::cartridge::{Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// Fun Play and Power Play: 8K banks in ROML, the bank number is scrambled over the bits of $DE00.
+pub struct FunPlay {
+    banks: Banks,
+    bank: usize,
+    exrom: bool,
+}
+
+impl FunPlay {
+    pub fn new(crt: &Crt) -> Result<FunPlay, String> {
+        Ok(FunPlay {
+            banks: Banks::from_crt(crt, &[0x8000])?,
+            bank: 0,
+            exrom: false,
+        })
+    }
+}
+
+impl Mapper for FunPlay {
+    fn exrom(&self) -> bool {
+        self.exrom
+    }
+
+    fn game(&self) -> bool {
+        true
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        Some(self.banks.read(self.bank, offset))
+    }
+
+    fn write_io1(&mut self, _loc: usize, value: u8) {
+        self.bank = (((value >> 3) & 0x07) | ((value & 0x01) << 3)) as usize;
+        match value & 0xC6 {
+            0x00 => self.exrom = false,
+            0x86 => self.exrom = true,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::emulator::cartridge::{CHIP_RAM, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// The generic 8K, 16K and Ultimax cartridges without bank switching, plain ROM in the ROML/ROMH
+/// windows.
+pub struct Generic {
+    roml: Vec<u8>,
+    romh: Vec<u8>,
+    exrom: bool,
+    game: bool,
+}
+
+impl Generic {
+    pub fn new(crt: &Crt) -> Result<Generic, String> {
+        let mut generic = Generic {
+            roml: Vec::new(),
+            romh: Vec::new(),
+            exrom: crt.exrom,
+            game: crt.game,
+        };
+        for chip in &crt.chips {
+            if chip.bank != 0 {
+                return Err(format!("Unexpected bank {} in a cartridge without bank switching", chip.bank));
+            }
+            if chip.chip_type == CHIP_RAM {
+                continue;
+            }
+            match chip.load_address {
+                // a 16K chip covers ROML and ROMH
+                0x8000 if chip.data.len() > 0x2000 => {
+                    generic.romh = chip.data[0x2000..].to_vec();
+                    generic.roml = chip.data[..0x2000].to_vec();
+                }
+                0x8000 => generic.roml = chip.data.clone(),
+                0xA000 | 0xE000 => generic.romh = chip.data.clone(),
+                // 4K Ultimax images only cover the upper half of ROMH
+                0xF000 => {
+                    generic.romh = vec![0xFF; 0x1000];
+                    generic.romh.extend_from_slice(&chip.data);
+                }
+                address => return Err(format!("Unsupported CHIP load address {:04X}", address)),
+            }
+        }
+        Ok(generic)
+    }
+}
+
+impl Mapper for Generic {
+    fn exrom(&self) -> bool {
+        self.exrom
+    }
+
+    fn game(&self) -> bool {
+        self.game
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        read_rom(&self.roml, offset)
+    }
+
+    fn read_romh(&mut self, offset: usize) -> Option<u8> {
+        read_rom(&self.romh, offset)
+    }
+
+    fn peek_romh(&self, offset: usize) -> Option<u8> {
+        read_rom(&self.romh, offset)
+    }
+}
+
+/// Smaller images are mirrored in their 8K window.
+fn read_rom(rom: &[u8], offset: usize) -> Option<u8> {
+    if rom.is_empty() {
+        None
+    } else {
+        Some(rom[offset % rom.len()])
+    }
+}
@@ -0,0 +1,36 @@
+use crate::emulator::cartridge::{Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// Magic Desk, Domark and HES Australia: 8K banks in ROML selected by bits 0-6 of $DE00, bit 7
+/// switches the cartridge off.
+pub struct MagicDesk {
+    banks: Banks,
+    register: u8,
+}
+
+impl MagicDesk {
+    pub fn new(crt: &Crt) -> Result<MagicDesk, String> {
+        Ok(MagicDesk {
+            banks: Banks::from_crt(crt, &[0x8000])?,
+            register: 0,
+        })
+    }
+}
+
+impl Mapper for MagicDesk {
+    fn exrom(&self) -> bool {
+        self.register & 0x80 != 0
+    }
+
+    fn game(&self) -> bool {
+        true
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        Some(self.banks.read((self.register & 0x7F) as usize, offset))
+    }
+
+    fn write_io1(&mut self, _loc: usize, value: u8) {
+        self.register = value;
+    }
+}
@@ -0,0 +1,343 @@
+use crate::emulator::bus::Bus;
+use crate::emulator::cartridge::action_replay::ActionReplay;
+use crate::emulator::cartridge::c64gs::C64Gs;
+use crate::emulator::cartridge::crt::Crt;
+use crate::emulator::cartridge::easy_flash::EasyFlash;
+use crate::emulator::cartridge::epyx_fastload::EpyxFastLoad;
+use crate::emulator::cartridge::fun_play::FunPlay;
+use crate::emulator::cartridge::generic::Generic;
+use crate::emulator::cartridge::magic_desk::MagicDesk;
+use crate::emulator::cartridge::ocean::Ocean;
+use crate::emulator::expansion_port::ExpansionPort;
+
+pub mod crt;
+mod action_replay;
+mod c64gs;
+mod easy_flash;
+mod epyx_fastload;
+mod fun_play;
+mod generic;
+mod magic_desk;
+mod ocean;
+
+/// CHIP packet types.
+const CHIP_RAM: u16 = 1;
+const CHIP_FLASH: u16 = 2;
+
+const BANK_SIZE: usize = 0x2000;
+
+/// The logic of one cartridge type: which ROM, RAM or flash is visible in the ROML/ROMH windows
+/// and in I/O1/I/O2, and how it drives the EXROM and GAME lines. Offsets and addresses are as in
+/// `ExpansionPort`.
+pub trait Mapper {
+    /// Levels of the EXROM and GAME lines, true is high (inactive). They may change after any
+    /// access or cycle.
+    fn exrom(&self) -> bool;
+    fn game(&self) -> bool;
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8>;
+
+    fn read_romh(&mut self, _offset: usize) -> Option<u8> {
+        None
+    }
+
+    /// ROMH as the VIC sees it in Ultimax mode, without side effects.
+    fn peek_romh(&self, _offset: usize) -> Option<u8> {
+        None
+    }
+
+    /// Writes while the ROML/ROMH window is mapped. Outside of Ultimax mode they also reach the
+    /// RAM below.
+    fn write_roml(&mut self, _offset: usize, _value: u8) {}
+
+    fn write_romh(&mut self, _offset: usize, _value: u8) {}
+
+    /// I/O1 at $DE00-$DEFF, `None` if the cartridge does not drive the data bus.
+    fn read_io1(&mut self, _loc: usize) -> Option<u8> {
+        None
+    }
+
+    fn write_io1(&mut self, _loc: usize, _value: u8) {}
+
+    /// I/O2 at $DF00-$DFFF, `None` if the cartridge does not drive the data bus.
+    fn read_io2(&mut self, _loc: usize) -> Option<u8> {
+        None
+    }
+
+    fn write_io2(&mut self, _loc: usize, _value: u8) {}
+
+    /// Called once per CPU cycle.
+    fn tick(&mut self) {}
+
+    /// Level of the NMI line, true is asserted.
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    /// Presses the freeze button, if the cartridge has one.
+    fn freeze(&mut self) {}
+
+    /// The CRT image with the changes made by the C64, if the cartridge has flash and it was
+    /// written to.
+    fn modified_image(&self) -> Option<Crt> {
+        None
+    }
+}
+
+/// A cartridge loaded from a CRT image, plugged into the expansion port.
+pub struct Cartridge {
+    name: String,
+    mapper: Box<dyn Mapper>,
+}
+
+impl Cartridge {
+    pub fn from_crt(crt: Crt) -> Result<Cartridge, String> {
+        let mapper: Box<dyn Mapper> = match crt.hardware_type {
+            0 => Box::new(Generic::new(&crt)?),
+            1 => Box::new(ActionReplay::new(&crt)?),
+            5 => Box::new(Ocean::new(&crt)?),
+            7 => Box::new(FunPlay::new(&crt)?),
+            10 => Box::new(EpyxFastLoad::new(&crt)?),
+            15 => Box::new(C64Gs::new(&crt)?),
+            19 => Box::new(MagicDesk::new(&crt)?),
+            32 => Box::new(EasyFlash::new(&crt)?),
+            hardware_type => return Err(format!("Unsupported cartridge hardware type {}", hardware_type)),
+        };
+        Ok(Cartridge { name: crt.name, mapper })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Presses the freeze button, if the cartridge has one.
+    pub fn freeze(&mut self) {
+        self.mapper.freeze();
+    }
+
+    pub fn modified_image(&self) -> Option<Crt> {
+        self.mapper.modified_image()
+    }
+}
+
+impl ExpansionPort for Cartridge {
+    fn read_io1(&mut self, loc: usize) -> Option<u8> {
+        self.mapper.read_io1(loc)
+    }
+
+    fn write_io1(&mut self, loc: usize, value: u8) {
+        self.mapper.write_io1(loc, value);
+    }
+
+    fn read_io2(&mut self, loc: usize) -> Option<u8> {
+        self.mapper.read_io2(loc)
+    }
+
+    fn write_io2(&mut self, loc: usize, value: u8) {
+        self.mapper.write_io2(loc, value);
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        self.mapper.read_roml(offset)
+    }
+
+    fn read_romh(&mut self, offset: usize) -> Option<u8> {
+        self.mapper.read_romh(offset)
+    }
+
+    fn peek_romh(&self, offset: usize) -> Option<u8> {
+        self.mapper.peek_romh(offset)
+    }
+
+    fn write_roml(&mut self, offset: usize, value: u8) {
+        self.mapper.write_roml(offset, value);
+    }
+
+    fn write_romh(&mut self, offset: usize, value: u8) {
+        self.mapper.write_romh(offset, value);
+    }
+
+    fn exrom(&self) -> bool {
+        self.mapper.exrom()
+    }
+
+    fn game(&self) -> bool {
+        self.mapper.game()
+    }
+
+    fn nmi(&self) -> bool {
+        self.mapper.nmi()
+    }
+
+    fn tick(&mut self, _c64: &mut dyn Bus, _ba_low: bool) {
+        self.mapper.tick();
+    }
+}
+
+/// 8K ROM banks indexed by the bank number of their CHIP packets, missing banks read as $FF.
+struct Banks {
+    data: Vec<u8>,
+}
+
+impl Banks {
+    /// Collects the ROM chips loaded at one of `load_addresses`.
+    fn from_crt(crt: &Crt, load_addresses: &[u16]) -> Result<Banks, String> {
+        let mut data = Vec::new();
+        for chip in &crt.chips {
+            if chip.chip_type == CHIP_RAM || !load_addresses.contains(&chip.load_address) {
+                continue;
+            }
+            if chip.data.len() > BANK_SIZE {
+                return Err(format!("Unexpected {} byte CHIP in bank {}", chip.data.len(), chip.bank));
+            }
+            let start = chip.bank as usize * BANK_SIZE;
+            if data.len() < start + BANK_SIZE {
+                data.resize(start + BANK_SIZE, 0xFF);
+            }
+            data[start..start + chip.data.len()].copy_from_slice(&chip.data);
+        }
+        if data.is_empty() {
+            return Err(String::from("The cartridge has no ROM"));
+        }
+        Ok(Banks { data })
+    }
+
+    /// Bank numbers past the end wrap around, like the unused address lines of smaller boards.
+    fn read(&self, bank: usize, offset: usize) -> u8 {
+        let banks = self.data.len() / BANK_SIZE;
+        self.data[(bank % banks) * BANK_SIZE + (offset & (BANK_SIZE - 1))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::bus::Bus;
+    use crate::emulator::cartridge::Cartridge;
+    use crate::emulator::cartridge::crt::Crt;
+    use crate::emulator::cartridge::crt::tests::crt_image;
+    use crate::emulator::expansion_port::ExpansionPort;
+    use crate::emulator::memory::Memory;
+    use crate::emulator::roms::Roms;
+
+    fn memory_with(image: Vec<u8>) -> Memory {
+        let mut memory = Memory::new(Roms::new());
+        memory.attach(Box::new(Cartridge::from_crt(Crt::parse(&image).unwrap()).unwrap()));
+        memory
+    }
+
+    #[test]
+    fn generic_cartridges() {
+        let mut memory = memory_with(crt_image(0, 0, 1, &[(0, 0x8000, vec![0x08; 0x2000])]));
+        assert_eq!(memory.read(0x8000), 0x08);
+        assert_eq!(memory.read(0xA000), 0x94, "BASIC stays visible");
+        memory.write(0x8000, 0x12);
+        memory.write(0x0001, 0x36);
+        assert_eq!(memory.read(0x8000), 0x12, "LORAM low shows the RAM below");
+
+        let mut memory = memory_with(crt_image(0, 0, 0, &[(0, 0x8000, [vec![0x08; 0x2000], vec![0x0A; 0x2000]].concat())]));
+        assert_eq!(memory.read(0x9FFF), 0x08);
+        assert_eq!(memory.read(0xA000), 0x0A);
+
+        let mut memory = memory_with(crt_image(0, 1, 0, &[(0, 0xE000, vec![0xEE; 0x2000])]));
+        assert_eq!(memory.read(0xFFFC), 0xEE);
+        memory.write(0xFFFC, 0x00);
+        assert_eq!(memory.read(0xFFFC), 0xEE);
+        assert_eq!(memory.get_from_gpu(0x3000), 0xEE);
+    }
+
+    fn banks(count: u16, load_address: u16) -> Vec<(u16, u16, Vec<u8>)> {
+        (0..count).map(|bank| (bank, load_address, vec![0x10 + bank as u8; 0x2000])).collect()
+    }
+
+    #[test]
+    fn bank_switching() {
+        let mut memory = memory_with(crt_image(5, 0, 0, &[banks(16, 0x8000), banks(32, 0xA000).split_off(16)].concat()));
+        memory.write(0xDE00, 0x83);
+        assert_eq!(memory.read(0x8000), 0x13);
+        memory.write(0xDE00, 0x91);
+        assert_eq!(memory.read(0xA000), 0x21);
+
+        let mut memory = memory_with(crt_image(19, 0, 1, &banks(16, 0x8000)));
+        memory.write(0xDE00, 0x05);
+        assert_eq!(memory.read(0x8000), 0x15);
+        memory.write(0xDE00, 0x80);
+        assert_eq!(memory.read(0x8000), 0x00, "switched off, RAM shows");
+
+        let mut memory = memory_with(crt_image(15, 0, 1, &banks(64, 0x8000)));
+        memory.write(0xDE2A, 0x00);
+        assert_eq!(memory.read(0x8000), 0x3A);
+        memory.read(0xDE00);
+        assert_eq!(memory.read(0x8000), 0x10);
+    }
+
+    #[test]
+    fn epyx_fastload_switches_off() {
+        let mut memory = memory_with(crt_image(10, 0, 1, &banks(1, 0x8000)));
+        for _ in 0..511 {
+            memory.cycle();
+        }
+        assert_eq!(memory.read(0x8000), 0x10);
+        for _ in 0..512 {
+            memory.cycle();
+        }
+        assert_eq!(memory.read(0x8000), 0x00, "RAM shows after the capacitor charged");
+        assert_eq!(memory.read(0xDF00), 0x10, "I/O2 stays visible");
+        memory.read(0xDE00);
+        assert_eq!(memory.read(0x8000), 0x10);
+    }
+
+    #[test]
+    fn action_replay_freeze() {
+        let mut cartridge = Cartridge::from_crt(Crt::parse(&crt_image(1, 0, 1, &banks(4, 0x8000))).unwrap()).unwrap();
+        cartridge.freeze();
+        assert!(cartridge.nmi());
+        let mut memory = Memory::new(Roms::new());
+        memory.attach(Box::new(cartridge));
+        assert_eq!(memory.read(0xFFFA), 0x10, "bank 0 in Ultimax mode");
+        memory.write(0xDE00, 0x40 | 0x20 | 0x10);
+        assert!(!memory.device::<Cartridge>().unwrap().nmi());
+        memory.write(0x8000, 0x55);
+        assert_eq!(memory.read(0x8000), 0x55, "RAM in ROML");
+        memory.write(0xDF00, 0x66);
+        assert_eq!(memory.read(0x9F00), 0x66, "I/O2 mirrors the last page of ROML");
+        memory.write(0xDE00, 0x10);
+        assert_eq!(memory.read(0x8000), 0x12);
+        assert_eq!(memory.read(0xDF00), 0x12);
+    }
+
+    #[test]
+    fn easy_flash_program_and_erase() {
+        let mut memory = memory_with(crt_image(32, 1, 0, &[(0, 0x8000, vec![0x11; 0x2000]), (0, 0xE000, vec![0xEE; 0x2000])]));
+        assert_eq!(memory.read(0xFFFC), 0xEE, "boots in Ultimax mode");
+        assert!(memory.device::<Cartridge>().unwrap().modified_image().is_none());
+
+        memory.write(0xDE00, 0x01);
+        for (loc, value) in [(0x8555, 0xAA), (0x82AA, 0x55), (0x8555, 0xA0), (0x8123, 0x42)] {
+            memory.write(loc, value);
+        }
+        assert_eq!(memory.read(0x8123), 0x42);
+        for (loc, value) in [(0xE555, 0xAA), (0xE2AA, 0x55), (0xE555, 0x90)] {
+            memory.write(loc, value);
+        }
+        assert_eq!(memory.read(0xE000), 0x01);
+        assert_eq!(memory.read(0xE001), 0xA4);
+        memory.write(0xE000, 0xF0);
+
+        memory.write(0xDE00, 0x00);
+        for (loc, value) in [(0x8555, 0xAA), (0x82AA, 0x55), (0x8555, 0x80), (0x8555, 0xAA), (0x82AA, 0x55), (0x8000, 0x30)] {
+            memory.write(loc, value);
+        }
+        assert_eq!(memory.read(0x8000), 0xFF);
+
+        memory.write(0xDF10, 0x99);
+        assert_eq!(memory.read(0xDF10), 0x99);
+        memory.write(0xDE02, 0x07);
+        assert_eq!(memory.read(0xA000), 0xEE, "16K mode");
+
+        let crt = Crt::parse(&memory.device::<Cartridge>().unwrap().modified_image().unwrap().to_bytes()).unwrap();
+        assert_eq!(crt.hardware_type, 32);
+        // the erased sector took bank 1 with it, only ROMH of bank 0 is left
+        assert_eq!(crt.chips.len(), 1);
+        assert_eq!(crt.chips[0].load_address, 0xA000);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::emulator::cartridge::{Banks, Mapper};
+use crate::emulator::cartridge::crt::Crt;
+
+/// Ocean type A (16K mode, up to 256K) and type B (8K mode, 512K). Writing to $DE00 selects one
+/// 8K bank, which shows in ROML and ROMH alike; the banks loaded at $A000 just have the higher
+/// numbers.
+pub struct Ocean {
+    banks: Banks,
+    bank: usize,
+    exrom: bool,
+    game: bool,
+}
+
+impl Ocean {
+    pub fn new(crt: &Crt) -> Result<Ocean, String> {
+        Ok(Ocean {
+            banks: Banks::from_crt(crt, &[0x8000, 0xA000])?,
+            bank: 0,
+            exrom: crt.exrom,
+            game: crt.game,
+        })
+    }
+}
+
+impl Mapper for Ocean {
+    fn exrom(&self) -> bool {
+        self.exrom
+    }
+
+    fn game(&self) -> bool {
+        self.game
+    }
+
+    fn read_roml(&mut self, offset: usize) -> Option<u8> {
+        Some(self.banks.read(self.bank, offset))
+    }
+
+    fn read_romh(&mut self, offset: usize) -> Option<u8> {
+        Some(self.banks.read(self.bank, offset))
+    }
+
+    fn write_io1(&mut self, _loc: usize, value: u8) {
+        self.bank = (value & 0x3F) as usize;
+    }
+}
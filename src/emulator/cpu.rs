@@ -1,6 +1,8 @@
 use crate::emulator::addressing::Addressing;
 use crate::emulator::logger::CpuLogger;
-use crate::emulator::memory::Memory;
+use crate::emulator::bus::Bus;
+use crate::emulator::opcodes::{Mode, OPCODES};
+use crate::emulator::opcodes::Operation::*;
 
 pub struct Cpu {
     pub pc: u16,
@@ -13,8 +15,16 @@ pub struct Cpu {
     pub opcode: u8,
     // debug
     pub inst: &'static str,
+    /// An interrupt sequence replaces the next opcode fetch.
     pub interrupted: bool,
     pub interrupted_started: bool,
+    /// Pending interrupt as polled at the end of the previous cycle.
+    pub interrupt_poll: bool,
+    pub irq_line: bool,
+    pub nmi_line: bool,
+    pub rdy: bool,
+    pub nmi_pending: bool,
+    pub jammed: bool,
 }
 
 // pub enum Flag {
@@ -41,18 +51,44 @@ impl Cpu {
             inst: "",
             interrupted: false,
             interrupted_started: false,
+            interrupt_poll: false,
+            irq_line: false,
+            nmi_line: false,
+            rdy: true,
+            nmi_pending: false,
+            jammed: false,
         }
     }
 
-    pub fn tick(&mut self, cpu_logger: &mut CpuLogger, memory: &mut Memory, addressing: &mut Addressing) -> Result<(), String> {
+    /// Runs one clock cycle.
+    pub fn tick(&mut self, cpu_logger: &mut CpuLogger, memory: &mut impl Bus, addressing: &mut Addressing) -> Result<(), String> {
+        let result = self.execute_cycle(cpu_logger, memory, addressing);
+        memory.cycle();
+        result
+    }
+
+    fn execute_cycle(&mut self, cpu_logger: &mut CpuLogger, memory: &mut impl Bus, addressing: &mut Addressing) -> Result<(), String> {
         // if self.tick_count == 2085967 {
         //     println!();
         // }
+        if self.jammed {
+            return Ok(());
+        }
+        if !self.rdy && !self.write_cycle() {
+            return Ok(());
+        }
+        // unlike RDY, DMA also takes the bus away in write cycles
+        if memory.dma() {
+            return Ok(());
+        }
         if self.state == 1 {
             cpu_logger.init(&self);
             if self.interrupted {
+                // the opcode is fetched but discarded, PC is not incremented
+                memory.get_from_word(self.pc);
                 self.state += 1;
                 self.interrupted_started = true;
+                self.poll_interrupts();
                 return Ok(());
             }
             self.interrupted_started = false;
@@ -61,118 +97,66 @@ impl Cpu {
                 //println!();
             }
             self.opcode = memory.get_from_word(pc);
+            self.inst = OPCODES[self.opcode as usize].mnemonic;
             cpu_logger.opcode(self.opcode);
             self.state += 1;
+            self.poll_interrupts();
             return Ok(());
         }
         if self.interrupted_started {
             self.state = addressing.implied_irq(self.state, self, memory)?;
         } else {
-            self.state = match self.opcode {
-                x @ 0x05 => addressing.zero_page_read(self.state, self, memory, Cpu::ora, x),
-                x @ 0x06 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::asl, x),
-                x @ 0x08 => addressing.implied_php_pha(self.state, self, memory, Cpu::php, x),
-                x @ 0x09 => addressing.immediate(self.state, self, memory, Cpu::ora, x),
-                x @ 0x0A => addressing.accumulator(self.state, self, Cpu::asl, x),
-                x @ 0x0D => addressing.absolute_read(self.state, self, memory, Cpu::ora, x),
-                x @ 0x10 => addressing.relative(self.state, self, memory, Cpu::bpl, x),
-                x @ 0x16 => addressing.zero_page_indexed_read_modify_write_x(self.state, self, memory, Cpu::asl, x),
-                x @ 0x18 => addressing.implied(self.state, self, Cpu::clc, x),
-                x @ 0x20 => addressing.absolute_jsr(self.state, self, memory, x),
-                x @ 0x24 => addressing.zero_page_read(self.state, self, memory, Cpu::bit, x),
-                x @ 0x26 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::rol, x),
-                x @ 0x28 => addressing.implied_plp_pla(self.state, self, memory, Cpu::plp, x),
-                x @ 0x29 => addressing.immediate(self.state, self, memory, Cpu::and, x),
-                x @ 0x2A => addressing.accumulator(self.state, self, Cpu::rol, x),
-                x @ 0x2C => addressing.absolute_read(self.state, self, memory, Cpu::bit, x),
-                x @ 0x2D => addressing.absolute_read(self.state, self, memory, Cpu::and, x),
-                x @ 0x30 => addressing.relative(self.state, self, memory, Cpu::bmi, x),
-                x @ 0x38 => addressing.implied(self.state, self, Cpu::sec, x),
-                x @ 0x40 => addressing.implied_rti(self.state, self, memory, x),
-                x @ 0x45 => addressing.zero_page_read(self.state, self, memory, Cpu::eor, x),
-                x @ 0x46 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::lsr, x),
-                x @ 0x48 => addressing.implied_php_pha(self.state, self, memory, Cpu::pha, x),
-                x @ 0x49 => addressing.immediate(self.state, self, memory, Cpu::eor, x),
-                x @ 0x4A => addressing.accumulator(self.state, self, Cpu::lsr, x),
-                x @ 0x4C => addressing.absolute_jmp(self.state, self, memory, x),
-                x @ 0x56 => addressing.zero_page_indexed_read_modify_write_x(self.state, self, memory, Cpu::lsr, x),
-                x @ 0x58 => addressing.implied(self.state, self, Cpu::cli, x),
-                x @ 0x60 => addressing.implied_rts(self.state, self, memory, x),
-                x @ 0x65 => addressing.zero_page_read(self.state, self, memory, Cpu::adc, x),
-                x @ 0x66 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::ror, x),
-                x @ 0x68 => addressing.implied_plp_pla(self.state, self, memory, Cpu::pla, x),
-                x @ 0x69 => addressing.immediate(self.state, self, memory, Cpu::adc, x),
-                x @ 0x6A => addressing.accumulator(self.state, self, Cpu::ror, x),
-                x @ 0x6C => addressing.absolute_indirect_jmp(self.state, self, memory, x),
-                x @ 0x70 => addressing.relative(self.state, self, memory, Cpu::bvs, x),
-                x @ 0x76 => addressing.zero_page_indexed_read_modify_write_x(self.state, self, memory, Cpu::ror, x),
-                x @ 0x78 => addressing.implied(self.state, self, Cpu::sei, x),
-                x @ 0x79 => addressing.absolute_indexed_read_y(self.state, self, memory, Cpu::adc, x),
-                x @ 0x84 => addressing.zero_page_write(self.state, self, memory, Cpu::sty, x),
-                x @ 0x85 => addressing.zero_page_write(self.state, self, memory, Cpu::sta, x),
-                x @ 0x86 => addressing.zero_page_write(self.state, self, memory, Cpu::stx, x),
-                x @ 0x88 => addressing.implied(self.state, self, Cpu::dey, x),
-                x @ 0x8A => addressing.implied(self.state, self, Cpu::txa, x),
-                x @ 0x8C => addressing.absolute_write(self.state, self, memory, Cpu::sty, x),
-                x @ 0x8D => addressing.absolute_write(self.state, self, memory, Cpu::sta, x),
-                x @ 0x8E => addressing.absolute_write(self.state, self, memory, Cpu::stx, x),
-                x @ 0x90 => addressing.relative(self.state, self, memory, Cpu::bcc, x),
-                x @ 0x91 => addressing.indirect_indexed_write(self.state, self, memory, Cpu::sta, x),
-                x @ 0x94 => addressing.zero_page_indexed_write_x(self.state, self, memory, Cpu::sty, x),
-                x @ 0x95 => addressing.zero_page_indexed_write_x(self.state, self, memory, Cpu::sta, x),
-                x @ 0x98 => addressing.implied(self.state, self, Cpu::tya, x),
-                x @ 0x99 => addressing.absolute_indexed_write_y(self.state, self, memory, Cpu::sta, x),
-                x @ 0x9A => addressing.implied(self.state, self, Cpu::txs, x),
-                x @ 0x9D => addressing.absolute_indexed_write_x(self.state, self, memory, Cpu::sta, x),
-                x @ 0xA0 => addressing.immediate(self.state, self, memory, Cpu::ldy, x),
-                x @ 0xA2 => addressing.immediate(self.state, self, memory, Cpu::ldx, x),
-                x @ 0xA4 => addressing.zero_page_read(self.state, self, memory, Cpu::ldy, x),
-                x @ 0xA5 => addressing.zero_page_read(self.state, self, memory, Cpu::lda, x),
-                x @ 0xA6 => addressing.zero_page_read(self.state, self, memory, Cpu::ldx, x),
-                x @ 0xA8 => addressing.implied(self.state, self, Cpu::tay, x),
-                x @ 0xA9 => addressing.immediate(self.state, self, memory, Cpu::lda, x),
-                x @ 0xAA => addressing.implied(self.state, self, Cpu::tax, x),
-                x @ 0xAC => addressing.absolute_read(self.state, self, memory, Cpu::ldy, x),
-                x @ 0xAD => addressing.absolute_read(self.state, self, memory, Cpu::lda, x),
-                x @ 0xAE => addressing.absolute_read(self.state, self, memory, Cpu::ldx, x),
-                x @ 0xB0 => addressing.relative(self.state, self, memory, Cpu::bcs, x),
-                x @ 0xB1 => addressing.indirect_indexed_read(self.state, self, memory, Cpu::lda, x),
-                x @ 0xB4 => addressing.zero_page_indexed_read_x(self.state, self, memory, Cpu::ldy, x),
-                x @ 0xB5 => addressing.zero_page_indexed_read_x(self.state, self, memory, Cpu::lda, x),
-                x @ 0xB9 => addressing.absolute_indexed_read_y(self.state, self, memory, Cpu::lda, x),
-                x @ 0xBA => addressing.implied(self.state, self, Cpu::tsx, x),
-                x @ 0xBD => addressing.absolute_indexed_read_x(self.state, self, memory, Cpu::lda, x),
-                x @ 0xC0 => addressing.immediate(self.state, self, memory, Cpu::cpy, x),
-                x @ 0xC4 => addressing.zero_page_read(self.state, self, memory, Cpu::cpy, x),
-                x @ 0xC5 => addressing.zero_page_read(self.state, self, memory, Cpu::cmp, x),
-                x @ 0xC6 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::dec, x),
-                x @ 0xC8 => addressing.implied(self.state, self, Cpu::iny, x),
-                x @ 0xC9 => addressing.immediate(self.state, self, memory, Cpu::cmp, x),
-                x @ 0xCA => addressing.implied(self.state, self, Cpu::dex, x),
-                x @ 0xCD => addressing.absolute_read(self.state, self, memory, Cpu::cmp, x),
-                x @ 0xCE => addressing.absolute_read_modify_write(self.state, self, memory, Cpu::dec, x),
-                x @ 0xD0 => addressing.relative(self.state, self, memory, Cpu::bne, x),
-                x @ 0xD1 => addressing.indirect_indexed_read(self.state, self, memory, Cpu::cmp, x),
-                x @ 0xD8 => addressing.implied(self.state, self, Cpu::cld, x),
-                x @ 0xDD => addressing.absolute_indexed_read_x(self.state, self, memory, Cpu::cmp, x),
-                x @ 0xE0 => addressing.immediate(self.state, self, memory, Cpu::cpx, x),
-                x @ 0xE4 => addressing.zero_page_read(self.state, self, memory, Cpu::cpx, x),
-                x @ 0xE5 => addressing.zero_page_read(self.state, self, memory, Cpu::sbc, x),
-                x @ 0xE6 => addressing.zero_page_read_modify_write(self.state, self, memory, Cpu::inc, x),
-                x @ 0xE8 => addressing.implied(self.state, self, Cpu::inx, x),
-                x @ 0xE9 => addressing.immediate(self.state, self, memory, Cpu::sbc, x),
-                x @ 0xEA => addressing.implied(self.state, self, Cpu::nop, x),
-                x @ 0xEC => addressing.absolute_read(self.state, self, memory, Cpu::cpx, x),
-                x @ 0xEE => addressing.absolute_read_modify_write(self.state, self, memory, Cpu::inc, x),
-                x @ 0xF0 => addressing.relative(self.state, self, memory, Cpu::beq, x),
-                x @ 0xF1 => addressing.indirect_indexed_read(self.state, self, memory, Cpu::sbc, x),
-                x @ 0xF9 => addressing.absolute_indexed_read_y(self.state, self, memory, Cpu::sbc, x),
-                x => Err(format!("Illegal opcode {:02X} at {:04X}", x, self.pc - 1))
+            let opcode = &OPCODES[self.opcode as usize];
+            let (state, x) = (self.state, self.opcode);
+            self.state = match (opcode.mode, opcode.operation) {
+                (Mode::Implied, Implied(inst)) => addressing.implied(state, self, memory, inst, x),
+                (Mode::Implied, Push(inst)) => addressing.implied_php_pha(state, self, memory, inst, x),
+                (Mode::Implied, Pull(inst)) => addressing.implied_plp_pla(state, self, memory, inst, x),
+                (Mode::Implied, Brk) => addressing.implied_brk(state, self, memory, x),
+                (Mode::Implied, Rts) => addressing.implied_rts(state, self, memory, x),
+                (Mode::Implied, Rti) => addressing.implied_rti(state, self, memory, x),
+                (Mode::Implied, Jam) => addressing.implied_jam(state, self, memory, x),
+                (Mode::Accumulator, Modify(inst)) => addressing.accumulator(state, self, memory, inst, x),
+                (Mode::Immediate, Read(inst)) => addressing.immediate(state, self, memory, inst, x),
+                (Mode::ZeroPage, Read(inst)) => addressing.zero_page_read(state, self, memory, inst, x),
+                (Mode::ZeroPage, Write(inst)) => addressing.zero_page_write(state, self, memory, inst, x),
+                (Mode::ZeroPage, Modify(inst)) => addressing.zero_page_read_modify_write(state, self, memory, inst, x),
+                (Mode::ZeroPageX, Read(inst)) => addressing.zero_page_indexed_read_x(state, self, memory, inst, x),
+                (Mode::ZeroPageX, Write(inst)) => addressing.zero_page_indexed_write_x(state, self, memory, inst, x),
+                (Mode::ZeroPageX, Modify(inst)) => addressing.zero_page_indexed_read_modify_write_x(state, self, memory, inst, x),
+                (Mode::ZeroPageY, Read(inst)) => addressing.zero_page_indexed_read_y(state, self, memory, inst, x),
+                (Mode::ZeroPageY, Write(inst)) => addressing.zero_page_indexed_write_y(state, self, memory, inst, x),
+                (Mode::Absolute, Read(inst)) => addressing.absolute_read(state, self, memory, inst, x),
+                (Mode::Absolute, Write(inst)) => addressing.absolute_write(state, self, memory, inst, x),
+                (Mode::Absolute, Modify(inst)) => addressing.absolute_read_modify_write(state, self, memory, inst, x),
+                (Mode::Absolute, Jmp) => addressing.absolute_jmp(state, self, memory, x),
+                (Mode::Absolute, Jsr) => addressing.absolute_jsr(state, self, memory, x),
+                (Mode::AbsoluteX, Read(inst)) => addressing.absolute_indexed_read_x(state, self, memory, inst, x),
+                (Mode::AbsoluteX, Write(inst)) => addressing.absolute_indexed_write_x(state, self, memory, inst, x),
+                (Mode::AbsoluteX, WriteUnstable(inst)) => addressing.absolute_indexed_write_unstable_x(state, self, memory, inst, x),
+                (Mode::AbsoluteX, Modify(inst)) => addressing.absolute_indexed_read_modify_write_x(state, self, memory, inst, x),
+                (Mode::AbsoluteY, Read(inst)) => addressing.absolute_indexed_read_y(state, self, memory, inst, x),
+                (Mode::AbsoluteY, Write(inst)) => addressing.absolute_indexed_write_y(state, self, memory, inst, x),
+                (Mode::AbsoluteY, WriteUnstable(inst)) => addressing.absolute_indexed_write_unstable_y(state, self, memory, inst, x),
+                (Mode::AbsoluteY, Modify(inst)) => addressing.absolute_indexed_read_modify_write_y(state, self, memory, inst, x),
+                (Mode::Indirect, Jmp) => addressing.absolute_indirect_jmp(state, self, memory, x),
+                (Mode::IndexedIndirect, Read(inst)) => addressing.indexed_indirect_read(state, self, memory, inst, x),
+                (Mode::IndexedIndirect, Write(inst)) => addressing.indexed_indirect_write(state, self, memory, inst, x),
+                (Mode::IndexedIndirect, Modify(inst)) => addressing.indexed_indirect_read_modify_write(state, self, memory, inst, x),
+                (Mode::IndirectIndexed, Read(inst)) => addressing.indirect_indexed_read(state, self, memory, inst, x),
+                (Mode::IndirectIndexed, Write(inst)) => addressing.indirect_indexed_write(state, self, memory, inst, x),
+                (Mode::IndirectIndexed, WriteUnstable(inst)) => addressing.indirect_indexed_write_unstable(state, self, memory, inst, x),
+                (Mode::IndirectIndexed, Modify(inst)) => addressing.indirect_indexed_read_modify_write(state, self, memory, inst, x),
+                (Mode::Relative, Branch(inst)) => addressing.relative(state, self, memory, inst, x),
+                _ => Err(format!("No bus sequence for opcode {:02X}", x)),
             }?;
         }
 
         if self.state == 1 {
+            self.interrupted = self.interrupt_poll;
             cpu_logger.log(&self, &addressing);
+        } else {
+            self.poll_interrupts();
         }
 
         Ok(())
@@ -180,7 +164,7 @@ impl Cpu {
 
     pub fn get_and_increment_pc(&mut self) -> u16 {
         let pc = self.pc;
-        self.pc += 1;
+        self.pc = self.pc.wrapping_add(1);
         pc
     }
 
@@ -188,6 +172,11 @@ impl Cpu {
         self.pc = ((pcl as u16) & 0x00FF) | (((pch as u16) << 8) & 0xFF00);
     }
 
+    /// B and bit 5 only exist on the stack, in the register bit 5 always reads as set and B as clear.
+    pub fn set_status_from_stack(&mut self, value: u8) {
+        self.p = (value & !0x10) | 0x20;
+    }
+
     pub fn set_negative_and_zero_flags(&mut self, value: u8) {
         self.p = (self.p & !0x80) | (value & 0x80);
         if value == 0 { self.p |= 0x02; } else { self.p &= !0x02; }
@@ -237,10 +226,40 @@ impl Cpu {
         (self.p & 0x40) > 0
     }
 
-    pub fn interrupt(&mut self) {
-        if !self.get_interrupt_flag() {
-            self.interrupted = true;
+    /// RDY low halts the CPU in its next read cycle, writes still go through. The VIC uses it to
+    /// take over the bus for badlines and sprite fetches.
+    pub fn set_rdy_line(&mut self, ready: bool) {
+        self.rdy = ready;
+    }
+
+    fn write_cycle(&self) -> bool {
+        if self.state == 1 {
+            return false;
+        }
+        if self.interrupted_started {
+            return (3..=5).contains(&self.state);
         }
+        OPCODES[self.opcode as usize].writes_in(self.state)
+    }
+
+    /// IRQ is level triggered, it is taken for as long as the line is held and I is clear.
+    pub fn set_irq_line(&mut self, asserted: bool) {
+        self.irq_line = asserted;
+    }
+
+    /// The 6502 decides at the end of the second to last cycle of an instruction whether an
+    /// interrupt follows it. That is why CLI, SEI and PLP only take effect one instruction later,
+    /// they change I in their last cycle.
+    fn poll_interrupts(&mut self) {
+        self.interrupt_poll = self.nmi_pending || (self.irq_line && !self.get_interrupt_flag());
+    }
+
+    /// NMI is edge triggered, only a high to low transition of the (active low) line is latched.
+    pub fn set_nmi_line(&mut self, asserted: bool) {
+        if asserted && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = asserted;
     }
 
     // pub fn set_flag(&mut self, flag: Flag) {
@@ -296,151 +315,170 @@ impl Cpu {
     }
 
     pub fn inc(&mut self, value: u8) -> u8 {
-        self.inst = "INC";
         let new_value = value.wrapping_add(1);
         self.set_negative_and_zero_flags(new_value);
         new_value
     }
 
     pub fn dec(&mut self, value: u8) -> u8 {
-        self.inst = "DEC";
         let new_value = value.wrapping_sub(1);
         self.set_negative_and_zero_flags(new_value);
         new_value
     }
 
     pub fn lda(&mut self, value: u8) {
-        self.inst = "LDA";
         self.a = value;
         self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn ldx(&mut self, value: u8) {
-        self.inst = "LDX";
         self.x = value;
         self.set_negative_and_zero_flags(self.x);
     }
 
     pub fn and(&mut self, value: u8) {
-        self.inst = "AND";
         self.a &= value;
         self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn bmi(&mut self) -> bool {
-        self.inst = "BMI";
         self.get_negative_flag()
     }
 
     pub fn bpl(&mut self) -> bool {
-        self.inst = "BPL";
         !self.get_negative_flag()
     }
 
     pub fn ora(&mut self, value: u8) {
-        self.inst = "ORA";
         self.a |= value;
         self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn bit(&mut self, value: u8) {
-        self.inst = "BIT";
         self.p = (self.p & !0xC0) | (value & 0xC0);
         self.set_zero_flag((self.a & value) == 0);
     }
 
     pub fn clc(&mut self) {
-        self.inst = "CLC";
         self.set_carry_flag(false);
     }
 
     pub fn sec(&mut self) {
-        self.inst = "SEC";
         self.set_carry_flag(true);
     }
 
     pub fn ldy(&mut self, value: u8) {
-        self.inst = "LDY";
         self.y = value;
         self.set_negative_and_zero_flags(self.y);
     }
 
     pub fn eor(&mut self, value: u8) {
-        self.inst = "EOR";
         self.a ^= value;
-        self.set_negative_and_zero_flags(value);
+        self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn adc(&mut self, value: u8) {
-        self.inst = "ADC";
-        self._adc(value);
+        if self.get_decimal_mode_flag() {
+            self.adc_decimal(value);
+        } else {
+            self.adc_binary(value);
+        }
+    }
+
+    fn adc_binary(&mut self, value: u8) {
+        let (newa, carry) = self.a.overflowing_add(value);
+        let (newa, carry2) = newa.overflowing_add(self.get_carry_flag() as u8);
+        self.set_overflow_flag(
+            ((newa & 0x80) != (value & 0x80)) && ((newa & 0x80) != (self.a & 0x80))
+        );
+        self.a = newa;
+        self.set_negative_and_zero_flags(self.a);
+        self.set_carry_flag(carry || carry2);
     }
 
-    fn _adc(&mut self, value: u8) {
+    /// NMOS behaviour: Z comes from the binary sum, N and V from the sum after only the low nibble
+    /// was adjusted, and C and A from the fully adjusted sum. This also defines the result for
+    /// operands that are not valid BCD.
+    fn adc_decimal(&mut self, value: u8) {
+        let carry = self.get_carry_flag() as u8;
+        let mut low = (self.a & 0x0F) + (value & 0x0F) + carry;
+        if low >= 0x0A {
+            low = ((low + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (self.a & 0xF0) as u16 + (value & 0xF0) as u16 + low as u16;
+        let signed_sum = (self.a & 0xF0) as i8 as i16 + (value & 0xF0) as i8 as i16 + low as i16;
+        self.set_zero_flag(self.a.wrapping_add(value).wrapping_add(carry) == 0);
+        self.p = (self.p & !0x80) | (sum as u8 & 0x80);
+        self.set_overflow_flag(!(-128..=127).contains(&signed_sum));
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.set_carry_flag(sum >= 0x100);
+        self.a = sum as u8;
+    }
+
+    pub fn sbc(&mut self, value: u8) {
         if self.get_decimal_mode_flag() {
-            let a = (self.a & 0x0F) % 10 + (((self.a >> 4) & 0x0F) % 10) * 10;
-            let value = (value & 0x0F) % 10 + (((value >> 4) & 0x0F) % 10) * 10;
-            let newa = a + value + (self.get_carry_flag() as u8);
-            self.set_carry_flag(newa > 99);
-            let newa = newa % 100;
-            self.a = (newa % 10) | ((newa / 10) << 4);
-            self.set_negative_and_zero_flags(self.a);
-            //todo overflow flag
+            self.sbc_decimal(value);
         } else {
-            let (newa, carry) = self.a.overflowing_add(value);
-            let (newa, carry2) = newa.overflowing_add(self.get_carry_flag() as u8);
-            self.set_overflow_flag(
-                ((newa & 0x80) != (value & 0x80)) && ((newa & 0x80) != (self.a & 0x80))
-            );
-            self.a = newa;
-            self.set_negative_and_zero_flags(self.a);
-            self.set_carry_flag(carry || carry2);
+            self.adc_binary(!value);
         }
     }
 
-    pub fn sbc(&mut self, value: u8) {
-        self.inst = "SBC";
-        self._adc(!value);
+    /// NMOS behaviour: all flags are the same as in binary mode, only A is adjusted.
+    fn sbc_decimal(&mut self, value: u8) {
+        let a = self.a as i16;
+        let value_i = value as i16;
+        let mut low = (a & 0x0F) - (value_i & 0x0F) + self.get_carry_flag() as i16 - 1;
+        if low < 0 {
+            low = ((low - 0x06) & 0x0F) - 0x10;
+        }
+        let mut difference = (a & 0xF0) - (value_i & 0xF0) + low;
+        if difference < 0 {
+            difference -= 0x60;
+        }
+        self.adc_binary(!value);
+        self.a = difference as u8;
     }
 
     pub fn pha(&mut self) -> u8 {
-        self.inst = "PHA";
         self.a
     }
 
     pub fn php(&mut self) -> u8 {
-        self.inst = "PHP";
-        self.p
+        self.p | 0x30
     }
 
     pub fn pla(&mut self, value: u8) {
-        self.inst = "PLA";
         self.a = value;
         self.set_negative_and_zero_flags(value);
     }
 
     pub fn plp(&mut self, value: u8) {
-        self.inst = "PLP";
-        self.p = value;
+        self.set_status_from_stack(value);
     }
 
     pub fn sei(&mut self) {
-        self.inst = "SEI";
         self.set_interrupt_flag(true);
     }
 
     pub fn cli(&mut self) {
-        self.inst = "CLI";
         self.set_interrupt_flag(false);
     }
 
+    pub fn clv(&mut self) {
+        self.set_overflow_flag(false);
+    }
+
+    pub fn sed(&mut self) {
+        self.set_decimal_mode_flag(true);
+    }
+
     pub fn cld(&mut self) {
-        self.inst = "CLD";
         self.set_decimal_mode_flag(false);
     }
 
     pub fn rol(&mut self, value: u8) -> u8 {
-        self.inst = "ROL";
         let new_carry = value & 0x80 > 0;
         let mut value = value.wrapping_shl(1);
         if self.get_carry_flag() {
@@ -452,7 +490,6 @@ impl Cpu {
     }
 
     pub fn ror(&mut self, value: u8) -> u8 {
-        self.inst = "ROR";
         let new_carry = value & 0x01 > 0;
         let mut value = value.wrapping_shr(1);
         if self.get_carry_flag() {
@@ -464,7 +501,6 @@ impl Cpu {
     }
 
     pub fn lsr(&mut self, value: u8) -> u8 {
-        self.inst = "LSR";
         self.set_carry_flag(value & 0x01 > 0);
         let value = value.wrapping_shr(1);
         self.set_negative_and_zero_flags(value);
@@ -472,7 +508,6 @@ impl Cpu {
     }
 
     pub fn asl(&mut self, value: u8) -> u8 {
-        self.inst = "ASL";
         self.set_carry_flag(value & 0x80 > 0);
         let value = value.wrapping_shl(1);
         self.set_negative_and_zero_flags(value);
@@ -480,128 +515,513 @@ impl Cpu {
     }
 
     pub fn txs(&mut self) {
-        self.inst = "TXS";
         self.sp = self.x;
     }
 
     pub fn tax(&mut self) {
-        self.inst = "TAX";
         self.x = self.a;
         self.set_negative_and_zero_flags(self.x);
     }
 
     pub fn txa(&mut self) {
-        self.inst = "TXA";
         self.a = self.x;
         self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn tsx(&mut self) {
-        self.inst = "TSX";
         self.x = self.sp;
-        self.set_negative_and_zero_flags(self.a);
+        self.set_negative_and_zero_flags(self.x);
     }
 
     pub fn tay(&mut self) {
-        self.inst = "TAY";
         self.y = self.a;
         self.set_negative_and_zero_flags(self.y);
     }
 
     pub fn tya(&mut self) {
-        self.inst = "TYA";
         self.a = self.y;
         self.set_negative_and_zero_flags(self.a);
     }
 
     pub fn cmp(&mut self, value: u8) {
-        self.inst = "CMP";
         let (value, overflow) = self.a.overflowing_sub(value);
         self.set_negative_and_zero_flags(value);
         self.set_carry_flag(!overflow);
     }
 
     pub fn cpy(&mut self, value: u8) {
-        self.inst = "CPY";
         let (value, overflow) = self.y.overflowing_sub(value);
         self.set_negative_and_zero_flags(value);
         self.set_carry_flag(!overflow);
     }
 
     pub fn cpx(&mut self, value: u8) {
-        self.inst = "CPX";
         let (value, overflow) = self.x.overflowing_sub(value);
         self.set_negative_and_zero_flags(value);
         self.set_carry_flag(!overflow);
     }
 
     pub fn bne(&mut self) -> bool {
-        self.inst = "BNE";
         !self.get_zero_flag()
     }
 
     pub fn bcs(&mut self) -> bool {
-        self.inst = "BCS";
         self.get_carry_flag()
     }
 
     pub fn bcc(&mut self) -> bool {
-        self.inst = "BCC";
         !self.get_carry_flag()
     }
 
+    pub fn bvc(&mut self) -> bool {
+        !self.get_overflow_flag()
+    }
+
     pub fn bvs(&mut self) -> bool {
-        self.inst = "BVS";
         self.get_overflow_flag()
     }
 
     pub fn beq(&mut self) -> bool {
-        self.inst = "BEQ";
         self.get_zero_flag()
     }
 
     pub fn stx(&mut self) -> u8 {
-        self.inst = "STX";
         self.x
     }
 
     pub fn sty(&mut self) -> u8 {
-        self.inst = "STY";
         self.y
     }
 
     pub fn sta(&mut self) -> u8 {
-        self.inst = "STA";
         self.a
     }
 
     pub fn dex(&mut self) {
-        self.inst = "DEX";
         self.x = self.x.wrapping_sub(1);
         self.set_negative_and_zero_flags(self.x);
     }
 
     pub fn dey(&mut self) {
-        self.inst = "DEY";
         self.y = self.y.wrapping_sub(1);
         self.set_negative_and_zero_flags(self.y);
     }
 
     pub fn iny(&mut self) {
-        self.inst = "INY";
         self.y = self.y.wrapping_add(1);
         self.set_negative_and_zero_flags(self.y);
     }
 
     pub fn inx(&mut self) {
-        self.inst = "INX";
         self.x = self.x.wrapping_add(1);
         self.set_negative_and_zero_flags(self.x);
     }
 
     pub fn nop(&mut self) {
-        self.inst = "NOP";
-        self.x = self.x.wrapping_add(1);
-        self.set_negative_and_zero_flags(self.x);
     }
-}
\ No newline at end of file
+
+    pub fn slo(&mut self, value: u8) -> u8 {
+        let value = self.asl(value);
+        self.ora(value);
+        value
+    }
+
+    pub fn rla(&mut self, value: u8) -> u8 {
+        let value = self.rol(value);
+        self.and(value);
+        value
+    }
+
+    pub fn sre(&mut self, value: u8) -> u8 {
+        let value = self.lsr(value);
+        self.eor(value);
+        value
+    }
+
+    pub fn rra(&mut self, value: u8) -> u8 {
+        let value = self.ror(value);
+        self.adc(value);
+        value
+    }
+
+    pub fn dcp(&mut self, value: u8) -> u8 {
+        let value = self.dec(value);
+        self.cmp(value);
+        value
+    }
+
+    pub fn isc(&mut self, value: u8) -> u8 {
+        let value = self.inc(value);
+        self.sbc(value);
+        value
+    }
+
+    pub fn sax(&mut self) -> u8 {
+        self.a & self.x
+    }
+
+    pub fn lax(&mut self, value: u8) {
+        self.a = value;
+        self.x = value;
+        self.set_negative_and_zero_flags(value);
+    }
+
+    pub fn anc(&mut self, value: u8) {
+        self.and(value);
+        self.set_carry_flag(self.get_negative_flag());
+    }
+
+    pub fn alr(&mut self, value: u8) {
+        self.and(value);
+        self.a = self.lsr(self.a);
+    }
+
+    pub fn arr(&mut self, value: u8) {
+        let and = self.a & value;
+        let carry = self.get_carry_flag();
+        self.a = (and >> 1) | ((carry as u8) << 7);
+        self.set_negative_and_zero_flags(self.a);
+        self.set_overflow_flag((and ^ self.a) & 0x40 != 0);
+        if self.get_decimal_mode_flag() {
+            if (and & 0x0F) + (and & 0x01) > 5 {
+                self.a = (self.a & 0xF0) | (self.a.wrapping_add(6) & 0x0F);
+            }
+            let fix_high = (and >> 4) + ((and >> 4) & 0x01) > 5;
+            if fix_high {
+                self.a = self.a.wrapping_add(0x60);
+            }
+            self.set_carry_flag(fix_high);
+        } else {
+            self.set_carry_flag(self.a & 0x40 != 0);
+        }
+    }
+
+    /// Unstable: the "magic" constant ORed into A depends on the chip and temperature, $EF is what
+    /// most C64s show.
+    pub fn ane(&mut self, value: u8) {
+        self.a = (self.a | 0xEF) & self.x & value;
+        self.set_negative_and_zero_flags(self.a);
+    }
+
+    /// Unstable, see `ane`. $EE is the value commonly seen on a C64.
+    pub fn lxa(&mut self, value: u8) {
+        self.a = (self.a | 0xEE) & value;
+        self.x = self.a;
+        self.set_negative_and_zero_flags(self.a);
+    }
+
+    pub fn sbx(&mut self, value: u8) {
+        let (value, overflow) = (self.a & self.x).overflowing_sub(value);
+        self.x = value;
+        self.set_negative_and_zero_flags(value);
+        self.set_carry_flag(!overflow);
+    }
+
+    pub fn las(&mut self, value: u8) {
+        let value = value & self.sp;
+        self.a = value;
+        self.x = value;
+        self.sp = value;
+        self.set_negative_and_zero_flags(value);
+    }
+
+    pub fn sha(&mut self, high_plus_one: u8) -> u8 {
+        self.a & self.x & high_plus_one
+    }
+
+    pub fn shx(&mut self, high_plus_one: u8) -> u8 {
+        self.x & high_plus_one
+    }
+
+    pub fn shy(&mut self, high_plus_one: u8) -> u8 {
+        self.y & high_plus_one
+    }
+
+    pub fn tas(&mut self, high_plus_one: u8) -> u8 {
+        self.sp = self.a & self.x;
+        self.sp & high_plus_one
+    }
+
+    pub fn nop_read(&mut self, _value: u8) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::addressing::Addressing;
+    use crate::emulator::cpu::Cpu;
+    use crate::emulator::cpu_tests::{Access, FlatRam};
+    use crate::emulator::keyboard::{Key, Keyboard};
+    use crate::emulator::logger::CpuLogger;
+    use crate::emulator::memory::cia2::Cia2;
+    use crate::emulator::timer_a::TimerARegisters;
+
+    /// Runs `program` at $0200 with the IRQ line asserted from cycle `irq_cycle` on and returns the
+    /// return address the IRQ pushed.
+    fn irq_return_address(program: &[u8], p: u8, irq_cycle: usize) -> u16 {
+        let mut ram = FlatRam::new(vec![0xEA; 0x10000]);
+        ram.data[0x0200..0x0200 + program.len()].copy_from_slice(program);
+        ram.data[0xFFFE] = 0x00;
+        ram.data[0xFFFF] = 0x03;
+        let mut cpu = Cpu::new();
+        cpu.pc = 0x0200;
+        cpu.sp = 0xFF;
+        cpu.p = p;
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        for cycle in 1..100 {
+            cpu.set_irq_line(cycle >= irq_cycle);
+            cpu.tick(&mut logger, &mut ram, &mut addressing).unwrap();
+            if cpu.pc == 0x0300 {
+                return (ram.data[0x01FE] as u16) | ((ram.data[0x01FF] as u16) << 8);
+            }
+        }
+        panic!("no IRQ");
+    }
+
+    /// Runs `program` at $0200 for `cycles` cycles with IRQ asserted if `irq` and NMI from cycle
+    /// `nmi_cycle` on, and returns the CPU and the bus accesses. NMI vectors to $0300, IRQ to $0400.
+    fn interrupt_run(program: &[u8], p: u8, irq: bool, nmi_cycle: usize, cycles: usize) -> (Cpu, Vec<Access>) {
+        let mut ram = FlatRam::new(vec![0xEA; 0x10000]);
+        ram.data[0x0200..0x0200 + program.len()].copy_from_slice(program);
+        ram.data[0xFFFA..0x10000].copy_from_slice(&[0x00, 0x03, 0x00, 0x00, 0x00, 0x04]);
+        ram.accesses = Some(Vec::new());
+        let mut cpu = Cpu::new();
+        cpu.pc = 0x0200;
+        cpu.sp = 0xFF;
+        cpu.p = p;
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        for cycle in 1..=cycles {
+            cpu.set_irq_line(irq);
+            cpu.set_nmi_line(cycle >= nmi_cycle);
+            cpu.tick(&mut logger, &mut ram, &mut addressing).unwrap();
+        }
+        (cpu, ram.accesses.unwrap())
+    }
+
+    /// P is the last value pushed by PHP and by the interrupt sequences.
+    fn pushed_p(accesses: &[Access]) -> u8 {
+        accesses.iter().rev().find_map(|access| match access {
+            Access::Write(0x0100..=0x01FF, p) => Some(*p),
+            _ => None,
+        }).expect("no P pushed")
+    }
+
+    #[test]
+    fn nmi_hijacks_brk() {
+        let (cpu, accesses) = interrupt_run(&[0x00, 0x00], 0x20, false, usize::MAX, 7);
+        assert_eq!(accesses[5..], [Access::Read(0xFFFE, 0x00), Access::Read(0xFFFF, 0x04)]);
+        assert_eq!(cpu.pc, 0x0400);
+
+        // an NMI arriving before P is pushed takes the NMI vector, B is still pushed set
+        let (cpu, accesses) = interrupt_run(&[0x00, 0x00], 0x20, false, 3, 7);
+        assert_eq!(accesses, [
+            Access::Read(0x0200, 0x00), Access::Read(0x0201, 0x00),
+            Access::Write(0x01FF, 0x02), Access::Write(0x01FE, 0x02), Access::Write(0x01FD, 0x30),
+            Access::Read(0xFFFA, 0x00), Access::Read(0xFFFB, 0x03),
+        ]);
+        assert_eq!(cpu.pc, 0x0300);
+        assert!(!cpu.nmi_pending);
+    }
+
+    #[test]
+    fn b_flag() {
+        // PHP and BRK push B set
+        assert_eq!(pushed_p(&interrupt_run(&[0x08], 0x20, false, usize::MAX, 3).1), 0x30);
+        assert_eq!(pushed_p(&interrupt_run(&[0x00, 0x00], 0x20, false, usize::MAX, 7).1), 0x30);
+        // IRQ and NMI push it clear
+        let (cpu, accesses) = interrupt_run(&[0xEA], 0x30, true, usize::MAX, 9);
+        assert_eq!(pushed_p(&accesses), 0x20);
+        assert_eq!(cpu.pc, 0x0400);
+        let (cpu, accesses) = interrupt_run(&[0xEA], 0x34, false, 1, 9);
+        assert_eq!(pushed_p(&accesses), 0x24);
+        assert_eq!(cpu.pc, 0x0300);
+    }
+
+    #[test]
+    fn nmi_falling_edge() {
+        let mut cpu = Cpu::new();
+        cpu.set_nmi_line(true);
+        assert!(cpu.nmi_pending);
+        cpu.nmi_pending = false;
+        // a held line does not trigger again, only releasing and asserting it does
+        cpu.set_nmi_line(true);
+        assert!(!cpu.nmi_pending);
+        cpu.set_nmi_line(false);
+        assert!(!cpu.nmi_pending);
+        cpu.set_nmi_line(true);
+        assert!(cpu.nmi_pending);
+
+        // held for 60 cycles the NMI is taken once, even with I set
+        let (_, accesses) = interrupt_run(&[0xEA], 0x24, false, 1, 60);
+        assert_eq!(accesses.iter().filter(|access| matches!(access, Access::Read(0xFFFA, _))).count(), 1);
+    }
+
+    #[test]
+    fn restore_and_cia2_share_the_nmi_line() {
+        let mut cpu = Cpu::new();
+        let mut keyboard = Keyboard::new();
+        let mut cia2 = Cia2::new();
+        cia2.set(0xDD0D, 0x81);
+        // wired as in Emulator::step
+        let update = |cpu: &mut Cpu, keyboard: &Keyboard, cia2: &Cia2| {
+            cpu.set_nmi_line(keyboard.restore_pressed() || cia2.nmi_asserted());
+            std::mem::take(&mut cpu.nmi_pending)
+        };
+        keyboard.change_key_state(Key::Restore, true);
+        assert!(update(&mut cpu, &keyboard, &cia2));
+        // the line is already low, a CIA2 interrupt while RESTORE is held is lost
+        cia2.interrupt_timer_a();
+        assert!(!update(&mut cpu, &keyboard, &cia2));
+        keyboard.change_key_state(Key::Restore, false);
+        assert!(!update(&mut cpu, &keyboard, &cia2));
+        // acknowledging it in $DD0D releases the line and re-arms the edge
+        cia2.get(0xDD0D);
+        assert!(!update(&mut cpu, &keyboard, &cia2));
+        cia2.interrupt_timer_a();
+        assert!(update(&mut cpu, &keyboard, &cia2));
+        keyboard.change_key_state(Key::Restore, true);
+        assert!(!update(&mut cpu, &keyboard, &cia2));
+    }
+
+    #[test]
+    fn rdy_halts_reads_only() {
+        // INC $10 and RDY going low before the given cycle
+        let run = |rdy_cycle: usize| {
+            let mut ram = FlatRam::new(vec![0xEA; 0x10000]);
+            ram.data[0x0200..0x0202].copy_from_slice(&[0xE6, 0x10]);
+            ram.data[0x0010] = 5;
+            let mut cpu = Cpu::new();
+            cpu.pc = 0x0200;
+            let mut logger = CpuLogger::new();
+            let mut addressing = Addressing::new();
+            for cycle in 1..20 {
+                cpu.set_rdy_line(cycle < rdy_cycle);
+                cpu.tick(&mut logger, &mut ram, &mut addressing).unwrap();
+            }
+            (ram.data[0x0010], cpu.pc)
+        };
+        assert_eq!(run(3), (5, 0x0202));
+        // both writes of the read-modify-write finish, the next opcode fetch does not happen
+        assert_eq!(run(4), (6, 0x0202));
+        assert_eq!(run(100), (6, 0x0209));
+    }
+
+    #[test]
+    fn irq_latency() {
+        // CLI clears I in its last cycle, the NOP after it still runs
+        assert_eq!(irq_return_address(&[0x58, 0xEA], 0x24, 1), 0x0202);
+        // SEI sets I too late to stop an IRQ that is already pending
+        assert_eq!(irq_return_address(&[0x78, 0xEA], 0x20, 1), 0x0201);
+        // a taken branch within the page ignores an IRQ arriving after its operand fetch
+        assert_eq!(irq_return_address(&[0xD0, 0x00, 0xEA], 0x20, 2), 0x0203);
+        assert_eq!(irq_return_address(&[0xD0, 0x00, 0xEA], 0x20, 1), 0x0202);
+    }
+
+    fn decimal_cpu(a: u8, carry: bool) -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.a = a;
+        cpu.set_decimal_mode_flag(true);
+        cpu.set_carry_flag(carry);
+        cpu
+    }
+
+    #[test]
+    fn decimal_adc() {
+        let mut cpu = decimal_cpu(0x58, true);
+        cpu.adc(0x46);
+        assert_eq!(cpu.a, 0x05);
+        assert!(cpu.get_carry_flag());
+
+        // Z follows the binary sum ($9A), N the half adjusted one ($A0)
+        let mut cpu = decimal_cpu(0x99, false);
+        cpu.adc(0x01);
+        assert_eq!(cpu.a, 0x00);
+        assert!(cpu.get_carry_flag());
+        assert!(!cpu.get_zero_flag());
+        assert!(cpu.get_negative_flag());
+
+        let mut cpu = decimal_cpu(0x79, true);
+        cpu.adc(0x00);
+        assert_eq!(cpu.a, 0x80);
+        assert!(cpu.get_overflow_flag());
+        assert!(cpu.get_negative_flag());
+
+        // invalid BCD
+        let mut cpu = decimal_cpu(0x1A, false);
+        cpu.adc(0x00);
+        assert_eq!(cpu.a, 0x20);
+    }
+
+    #[test]
+    fn decimal_sbc() {
+        let mut cpu = decimal_cpu(0x00, true);
+        cpu.sbc(0x01);
+        assert_eq!(cpu.a, 0x99);
+        assert!(!cpu.get_carry_flag());
+        assert!(cpu.get_negative_flag());
+
+        let mut cpu = decimal_cpu(0x46, true);
+        cpu.sbc(0x12);
+        assert_eq!(cpu.a, 0x34);
+        assert!(cpu.get_carry_flag());
+
+        let mut cpu = decimal_cpu(0x40, true);
+        cpu.sbc(0x13);
+        assert_eq!(cpu.a, 0x27);
+        assert!(cpu.get_carry_flag());
+    }
+
+    /// A, N, V, Z and C of a decimal ADC as VICE computes them, digit by digit.
+    fn reference_adc(a: u8, value: u8, carry: bool) -> (u8, bool, bool, bool, bool) {
+        let (a, value, carry) = (a as u32, value as u32, carry as u32);
+        let mut low = (a & 0x0F) + (value & 0x0F) + carry;
+        if low > 0x09 {
+            low += 0x06;
+        }
+        let mut sum = (low & 0x0F) + (a & 0xF0) + (value & 0xF0) + if low > 0x0F { 0x10 } else { 0 };
+        let zero = (a + value + carry) & 0xFF == 0;
+        let negative = sum & 0x80 != 0;
+        let overflow = (a ^ sum) & 0x80 != 0 && (a ^ value) & 0x80 == 0;
+        if sum & 0x1F0 > 0x90 {
+            sum += 0x60;
+        }
+        (sum as u8, negative, overflow, zero, sum & 0xFF0 > 0xF0)
+    }
+
+    /// The same for SBC, the flags come from the binary difference.
+    fn reference_sbc(a: u8, value: u8, carry: bool) -> (u8, bool, bool, bool, bool) {
+        let (a, value, borrow) = (a as u32, value as u32, !carry as u32);
+        let binary = a.wrapping_sub(value).wrapping_sub(borrow);
+        let mut low = (a & 0x0F).wrapping_sub(value & 0x0F).wrapping_sub(borrow);
+        let mut difference = if low & 0x10 != 0 {
+            low = low.wrapping_sub(0x06);
+            (low & 0x0F) | (a & 0xF0).wrapping_sub(value & 0xF0).wrapping_sub(0x10)
+        } else {
+            (low & 0x0F) | (a & 0xF0).wrapping_sub(value & 0xF0)
+        };
+        if difference & 0x100 != 0 {
+            difference = difference.wrapping_sub(0x60);
+        }
+        let overflow = (a ^ binary) & 0x80 != 0 && (a ^ value) & 0x80 != 0;
+        (difference as u8, binary & 0x80 != 0, overflow, binary & 0xFF == 0, binary < 0x100)
+    }
+
+    #[test]
+    fn decimal_exhaustive() {
+        for a in 0..=0xFF {
+            for value in 0..=0xFF {
+                for carry in [false, true] {
+                    let flags = |cpu: &Cpu| (cpu.a, cpu.get_negative_flag(), cpu.get_overflow_flag(), cpu.get_zero_flag(), cpu.get_carry_flag());
+                    let mut cpu = decimal_cpu(a, carry);
+                    cpu.adc(value);
+                    assert_eq!(flags(&cpu), reference_adc(a, value, carry), "ADC {:02X} + {:02X} + {}", a, value, carry as u8);
+                    let mut cpu = decimal_cpu(a, carry);
+                    cpu.sbc(value);
+                    assert_eq!(flags(&cpu), reference_sbc(a, value, carry), "SBC {:02X} - {:02X} - {}", a, value, !carry as u8);
+                }
+            }
+        }
+    }
+}
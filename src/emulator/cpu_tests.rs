@@ -0,0 +1,169 @@
+//! Runs Klaus Dormann's 6502 test suite (https://github.com/Klaus2m5/6502_65C02_functional_tests)
+//! on a flat 64K RAM bus. The binaries are not part of the repository, so the tests are ignored
+//! by default. Put them in `tests/roms` and run `cargo test -- --ignored`:
+//!
+//! * `6502_functional_test.bin` - the 64K image from `bin_files`, loaded at $0000
+//! * `6502_decimal_test.bin` - assembled from `6502_decimal_test.a65` (org $0200), loaded at $0200
+//! * `6502_interrupt_test.bin` - assembled from `6502_interrupt_test.a65` with I_port at $BFFC,
+//!   the 64K image loaded at $0000
+//!
+//! ```text
+//! git clone https://github.com/Klaus2m5/6502_65C02_functional_tests
+//! mkdir -p tests/roms
+//! cp 6502_65C02_functional_tests/bin_files/6502_functional_test.bin tests/roms/
+//! ```
+//!
+//! The other two are assembled from the sources in that repository with `as65`.
+//!
+//! The trap tests always run, with a small program in the same style.
+
+use std::fs;
+use std::path::PathBuf;
+
+use crate::emulator::addressing::Addressing;
+use crate::emulator::bus::Bus;
+use crate::emulator::cpu::Cpu;
+use crate::emulator::logger::CpuLogger;
+
+const MAX_CYCLES: u64 = 500_000_000;
+const TEST_CASE: u16 = 0x0200;
+
+/// One bus cycle as recorded by `FlatRam`.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Access {
+    Read(u16, u8),
+    Write(u16, u8),
+}
+
+pub(crate) struct FlatRam {
+    pub(crate) data: Vec<u8>,
+    /// Every access when set, left off for the long running suites.
+    pub(crate) accesses: Option<Vec<Access>>,
+}
+
+impl FlatRam {
+    pub(crate) fn new(data: Vec<u8>) -> FlatRam {
+        FlatRam { data, accesses: None }
+    }
+}
+
+impl Bus for FlatRam {
+    fn read(&mut self, address: u16) -> u8 {
+        let value = self.data[address as usize];
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(Access::Read(address, value));
+        }
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        if let Some(accesses) = &mut self.accesses {
+            accesses.push(Access::Write(address, value));
+        }
+        self.data[address as usize] = value;
+    }
+}
+
+struct TestRun {
+    cpu: Cpu,
+    ram: FlatRam,
+}
+
+impl TestRun {
+    fn load(file: &str, load_address: u16, start: u16) -> TestRun {
+        let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "roms", file].iter().collect();
+        let image = fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        TestRun::new(&image, load_address, start)
+    }
+
+    fn new(image: &[u8], load_address: u16, start: u16) -> TestRun {
+        let mut data = vec![0; 0x10000];
+        let load_address = load_address as usize;
+        let length = image.len().min(data.len() - load_address);
+        data[load_address..load_address + length].copy_from_slice(&image[..length]);
+        let mut cpu = Cpu::new();
+        cpu.pc = start;
+        cpu.sp = 0xFF;
+        TestRun { cpu, ram: FlatRam::new(data) }
+    }
+
+    /// Runs until an instruction jumps or branches to itself, or until one of `stop_opcodes` is
+    /// about to be executed, and returns the address it stopped at. `interrupt_port` is polled
+    /// after every cycle, bit 0 drives IRQ and bit 1 NMI.
+    fn run(&mut self, stop_opcodes: &[u8], interrupt_port: Option<u16>) -> Result<u16, String> {
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        let mut instruction_pc = None;
+        for _ in 0..MAX_CYCLES {
+            if self.cpu.state == 1 {
+                let pc = self.cpu.pc;
+                if instruction_pc == Some(pc) || stop_opcodes.contains(&self.ram.data[pc as usize]) {
+                    return Ok(pc);
+                }
+                instruction_pc = Some(pc);
+            }
+            self.cpu.tick(&mut logger, &mut self.ram, &mut addressing)?;
+            if self.cpu.jammed {
+                return Err(format!("CPU jammed at ${:04X}", self.cpu.pc));
+            }
+            if let Some(port) = interrupt_port {
+                let value = self.ram.data[port as usize];
+                self.cpu.set_irq_line(value & 0x01 == 0x01);
+                self.cpu.set_nmi_line(value & 0x02 == 0x02);
+            }
+        }
+        Err(format!("no trap after {} cycles", MAX_CYCLES))
+    }
+
+    fn expect_trap_at(&mut self, success: u16, interrupt_port: Option<u16>) {
+        let pc = self.run(&[], interrupt_port).unwrap();
+        assert_eq!(pc, success, "trapped at ${:04X} in test case ${:02X}", pc, self.ram.data[TEST_CASE as usize]);
+    }
+}
+
+#[test]
+#[ignore = "needs tests/roms/6502_functional_test.bin"]
+fn functional_test() {
+    let mut run = TestRun::load("6502_functional_test.bin", 0x0000, 0x0400);
+    run.expect_trap_at(0x3469, None);
+}
+
+#[test]
+#[ignore = "needs tests/roms/6502_decimal_test.bin"]
+fn decimal_test() {
+    let mut run = TestRun::load("6502_decimal_test.bin", 0x0200, 0x0200);
+    // the test ends with BRK or the 65C02 STP ($DB), ERROR at $0B is 0 if all results matched
+    let pc = run.run(&[0x00, 0xDB], None).unwrap();
+    assert_eq!(run.ram.data[0x000B], 0, "decimal test failed, stopped at ${:04X}", pc);
+}
+
+#[test]
+#[ignore = "needs tests/roms/6502_interrupt_test.bin"]
+fn interrupt_test() {
+    let mut run = TestRun::load("6502_interrupt_test.bin", 0x0000, 0x0400);
+    run.ram.data[0xBFFC] = 0;
+    run.expect_trap_at(0x06F5, Some(0xBFFC));
+}
+
+/// Test case 1 compares $80 with `expected` and traps at $0409 if it differs, success is the
+/// trap at $040B.
+fn trap_program(expected: u8) -> TestRun {
+    let program = [
+        0xA9, 0x01, 0x8D, 0x00, 0x02, // LDA #1, STA $0200
+        0xA9, 0x80, 0xC9, expected, // LDA #$80, CMP #expected
+        0xD0, 0xFE, // BNE *
+        0x4C, 0x0B, 0x04, // JMP *
+    ];
+    TestRun::new(&program, 0x0400, 0x0400)
+}
+
+#[test]
+fn trap_at_success() {
+    trap_program(0x80).expect_trap_at(0x040B, None);
+}
+
+#[test]
+#[should_panic(expected = "trapped at $0409 in test case $01")]
+fn trap_reports_the_test_case() {
+    trap_program(0x7F).expect_trap_at(0x040B, None);
+}
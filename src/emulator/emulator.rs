@@ -1,11 +1,17 @@
 use std::time::Duration;
 
 use crate::emulator::addressing::Addressing;
+use crate::emulator::bus::Bus;
+use crate::emulator::cartridge::Cartridge;
+use crate::emulator::cartridge::crt::Crt;
 use crate::emulator::cpu::Cpu;
+use crate::emulator::expansion_port::ExpansionPort;
 use crate::emulator::gpu::Gpu;
+use crate::emulator::irq_line::{IrqLine, IrqSource};
 use crate::emulator::keyboard::{Key, Keyboard};
 use crate::emulator::logger::CpuLogger;
 use crate::emulator::memory::Memory;
+use crate::emulator::roms::Roms;
 use crate::emulator::timer_a::TimerA;
 
 // const MASTER_CLOCK_PAL: u128 = 17_734_475;
@@ -28,28 +34,66 @@ pub struct Emulator {
     addressing: Addressing,
     cpu_logger: CpuLogger,
     timer_a: TimerA,
+    cia2_timer_a: TimerA,
+    irq_line: IrqLine,
     keyboard: Keyboard,
 }
 
 impl Emulator {
-    pub fn new() -> Emulator {
-        let mut memory = Memory::new();
-        let mut cpu = Cpu::new();
-        let low = memory.get_from_word(0xFFFC);
-        let high = memory.get_from_word(0xFFFD);
-        cpu.set_pc(low, high);
-        Emulator {
+    pub fn new(roms: Roms) -> Emulator {
+        let mut emulator = Emulator {
             tick_count: 0,
-            memory,
-            cpu,
+            memory: Memory::new(roms),
+            cpu: Cpu::new(),
             gpu: Gpu::new(),
             addressing: Addressing::new(),
             cpu_logger: CpuLogger::new(),
             timer_a: TimerA::new(),
+            cia2_timer_a: TimerA::new(),
+            irq_line: IrqLine::new(),
             keyboard: Keyboard::new(),
+        };
+        emulator.reset();
+        emulator
+    }
+
+    /// Restarts the CPU at the reset vector, memory and devices keep their state.
+    pub fn reset(&mut self) {
+        self.cpu = Cpu::new();
+        self.addressing = Addressing::new();
+        let low = self.memory.get_from_word(0xFFFC);
+        let high = self.memory.get_from_word(0xFFFD);
+        self.cpu.set_pc(low, high);
+    }
+
+    /// Plugs a device into the expansion port and resets, so that cartridges can take over
+    /// through their reset vector.
+    pub fn attach(&mut self, device: Box<dyn ExpansionPort>) {
+        self.memory.attach(device);
+        self.reset();
+    }
+
+    /// The first attached device of type `T`.
+    pub fn device<T: ExpansionPort>(&self) -> Option<&T> {
+        self.memory.device()
+    }
+
+    pub fn device_mut<T: ExpansionPort>(&mut self) -> Option<&mut T> {
+        self.memory.device_mut()
+    }
+
+    /// Presses the freeze button of the cartridge, if it has one.
+    pub fn freeze_cartridge(&mut self) {
+        if let Some(cartridge) = self.device_mut::<Cartridge>() {
+            cartridge.freeze();
         }
     }
 
+    /// The cartridge image with the changes written to its flash, to be saved over the CRT file.
+    pub fn modified_cartridge(&self) -> Option<Crt> {
+        self.device::<Cartridge>().and_then(|c| c.modified_image())
+    }
+
     pub fn step(&mut self, elapsed: Duration) -> Result<(), String> {
         let want_ticks = ((elapsed.as_nanos() * CLOCK) / NANOS_PER_SEC) as u64;
         while self.tick_count < want_ticks {
@@ -73,9 +117,23 @@ impl Emulator {
             if self.tick_count == 2118528 {
                 //debug!("{}", self.tick_count);
             }
-            self.gpu.tick(&self.memory);
-            self.timer_a.tick(&mut self.cpu, self.memory.cia1());
-            self.cpu.tick(&mut self.cpu_logger, &mut self.memory, &mut self.addressing)?;
+            self.gpu.tick(&mut self.memory);
+            self.cpu.set_rdy_line(!self.gpu.ba_low());
+            self.memory.set_vic_ba_low(self.gpu.ba_low());
+            self.timer_a.tick(self.memory.cia1());
+            self.cia2_timer_a.tick(self.memory.cia2_mut());
+            self.irq_line.set(IrqSource::Vic, self.memory.gpu().irq_asserted());
+            self.irq_line.set(IrqSource::Cia1, self.memory.cia1().irq_asserted());
+            self.irq_line.set(IrqSource::ExpansionPort, self.memory.expansion_port_irq());
+            self.cpu.set_irq_line(self.irq_line.asserted());
+            self.cpu.set_nmi_line(self.keyboard.restore_pressed() || self.memory.cia2().nmi_asserted() || self.memory.expansion_port_nmi());
+            if self.memory.expansion_port_reset() {
+                // the CPU starts over once the line is released, the devices keep running
+                self.reset();
+                self.memory.cycle();
+            } else {
+                self.cpu.tick(&mut self.cpu_logger, &mut self.memory, &mut self.addressing)?;
+            }
             self.tick_count += 1;
         }
         Ok(())
@@ -84,6 +142,10 @@ impl Emulator {
     pub fn key_change(&mut self, key: Key, pressed: bool) {
         self.keyboard.change_key_state(key, pressed);
     }
+
+    pub fn is_jammed(&self) -> bool {
+        self.cpu.jammed
+    }
 }
 
 // #[cfg(test)]
@@ -136,4 +198,4 @@ impl Emulator {
 //         assert_ne!(e.sub_tick, 1);
 //         assert_eq!(e.high, 2);
 //     }
-// }
\ No newline at end of file
+// }
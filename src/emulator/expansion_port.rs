@@ -0,0 +1,148 @@
+use std::any::Any;
+
+use crate::emulator::bus::Bus;
+
+/// A device plugged into the expansion port: cartridges, RAM expansions, or anything else that
+/// decodes I/O1 ($DE00-$DEFF), I/O2 ($DF00-$DFFF) or the ROML/ROMH windows and drives the port
+/// lines. Several devices can be attached, as with pass-through ports; every device sees every
+/// access and the first one that answers a read drives the data bus.
+///
+/// Offsets are relative to the start of the 8K ROM window, `loc` is the full address. All lines
+/// are open collector: EXROM and GAME are levels, true is high (inactive), the others are true
+/// while the device asserts them.
+pub trait ExpansionPort: Any {
+    /// `None` if the device does not drive the data bus.
+    fn read_io1(&mut self, _loc: usize) -> Option<u8> {
+        None
+    }
+
+    fn write_io1(&mut self, _loc: usize, _value: u8) {}
+
+    fn read_io2(&mut self, _loc: usize) -> Option<u8> {
+        None
+    }
+
+    fn write_io2(&mut self, _loc: usize, _value: u8) {}
+
+    /// $8000-$9FFF, when EXROM and GAME map ROML.
+    fn read_roml(&mut self, _offset: usize) -> Option<u8> {
+        None
+    }
+
+    /// $A000-$BFFF, or $E000-$FFFF in Ultimax mode.
+    fn read_romh(&mut self, _offset: usize) -> Option<u8> {
+        None
+    }
+
+    /// ROMH as the VIC sees it in Ultimax mode, without side effects.
+    fn peek_romh(&self, _offset: usize) -> Option<u8> {
+        None
+    }
+
+    /// Writes while a ROM window is mapped. Outside of Ultimax mode they also reach the RAM below.
+    fn write_roml(&mut self, _offset: usize, _value: u8) {}
+
+    fn write_romh(&mut self, _offset: usize, _value: u8) {}
+
+    /// Sees every write the CPU or a DMA makes, wherever it goes.
+    fn snoop_write(&mut self, _address: u16, _value: u8) {}
+
+    fn exrom(&self) -> bool {
+        true
+    }
+
+    fn game(&self) -> bool {
+        true
+    }
+
+    fn irq(&self) -> bool {
+        false
+    }
+
+    fn nmi(&self) -> bool {
+        false
+    }
+
+    /// Halts the CPU, the device masters the bus in `tick`.
+    fn dma(&self) -> bool {
+        false
+    }
+
+    /// Holds the machine in reset.
+    fn reset(&self) -> bool {
+        false
+    }
+
+    /// Called once per CPU cycle with the C64 side of the bus, for devices that do DMA.
+    /// `ba_low` is the VIC holding the bus for itself, DMA has to wait.
+    fn tick(&mut self, _c64: &mut dyn Bus, _ba_low: bool) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::memory::Memory;
+    use crate::emulator::roms::Roms;
+
+    /// Test hardware as a third party would write it: a register at $DE00 that maps an 8K ROM
+    /// and raises IRQ, and a one byte DMA to $0400 on writes to $DF00.
+    struct TestDevice {
+        register: u8,
+        dma_value: Option<u8>,
+    }
+
+    impl ExpansionPort for TestDevice {
+        fn read_io1(&mut self, _loc: usize) -> Option<u8> {
+            Some(self.register)
+        }
+
+        fn write_io1(&mut self, _loc: usize, value: u8) {
+            self.register = value;
+        }
+
+        fn write_io2(&mut self, _loc: usize, value: u8) {
+            self.dma_value = Some(value);
+        }
+
+        fn read_roml(&mut self, offset: usize) -> Option<u8> {
+            Some(offset as u8)
+        }
+
+        fn exrom(&self) -> bool {
+            self.register & 0x01 == 0
+        }
+
+        fn irq(&self) -> bool {
+            self.register & 0x80 != 0
+        }
+
+        fn dma(&self) -> bool {
+            self.dma_value.is_some()
+        }
+
+        fn tick(&mut self, c64: &mut dyn Bus, _ba_low: bool) {
+            if let Some(value) = self.dma_value.take() {
+                c64.write(0x0400, value);
+            }
+        }
+    }
+
+    #[test]
+    fn third_party_device() {
+        let mut memory = Memory::new(Roms::new());
+        memory.attach(Box::new(TestDevice { register: 0, dma_value: None }));
+        assert_eq!(memory.read(0xDF00), 0xFF, "open bus where nobody answers");
+        assert_eq!(memory.read(0x8005), 0x00);
+        memory.write(0xDE00, 0x81);
+        assert_eq!(memory.read(0xDE00), 0x81);
+        assert_eq!(memory.read(0x8005), 0x05, "EXROM maps ROML");
+        assert!(memory.expansion_port_irq());
+
+        memory.write(0xDF00, 0x42);
+        assert!(memory.dma());
+        memory.cycle();
+        assert!(!memory.dma());
+        assert_eq!(memory.read(0x0400), 0x42);
+        assert_eq!(memory.device::<TestDevice>().unwrap().register, 0x81);
+    }
+}
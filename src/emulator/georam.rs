@@ -0,0 +1,100 @@
+use std::fs;
+
+use crate::emulator::expansion_port::ExpansionPort;
+
+const MIN_SIZE: usize = 0x80000;
+const MAX_SIZE: usize = 0x400000;
+const BANK_SIZE: usize = 0x4000;
+
+/// geoRAM and NeoRAM: paged RAM seen through a 256 byte window at $DE00. The write-only registers
+/// at $DFFE and $DFFF select the 256 byte page within a 16K bank and the bank.
+pub struct GeoRam {
+    ram: Vec<u8>,
+    page: u8,
+    bank: u8,
+}
+
+impl GeoRam {
+    /// `size` in bytes, a power of two from 512K to 4MB.
+    pub fn new(size: usize) -> Result<GeoRam, String> {
+        if !size.is_power_of_two() || !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            return Err(format!("Unsupported geoRAM size {}K", size / 1024));
+        }
+        Ok(GeoRam {
+            ram: vec![0; size],
+            page: 0,
+            bank: 0,
+        })
+    }
+
+    /// Restores the contents saved in `path`, or starts out empty if there is no such file yet.
+    pub fn load(path: &str, size: usize) -> Result<GeoRam, String> {
+        let mut georam = GeoRam::new(size)?;
+        match fs::read(path) {
+            Ok(data) if data.len() == size => georam.ram = data,
+            Ok(data) => return Err(format!("'{}' has {} bytes, expected {}", path, data.len(), size)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(format!("Error loading geoRAM '{}': {}", path, e)),
+        }
+        Ok(georam)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, &self.ram)
+            .map_err(|e| format!("Error saving geoRAM '{}': {}", path, e))
+    }
+
+    fn address(&self, loc: usize) -> usize {
+        (self.bank as usize * BANK_SIZE + (self.page & 0x3F) as usize * 0x100 + (loc & 0xFF)) & (self.ram.len() - 1)
+    }
+}
+
+impl ExpansionPort for GeoRam {
+    /// The window at $DE00-$DEFF.
+    fn read_io1(&mut self, loc: usize) -> Option<u8> {
+        Some(self.ram[self.address(loc)])
+    }
+
+    fn write_io1(&mut self, loc: usize, value: u8) {
+        let address = self.address(loc);
+        self.ram[address] = value;
+    }
+
+    /// The registers at $DFFE and $DFFF, mirrored through I/O2.
+    fn write_io2(&mut self, loc: usize, value: u8) {
+        if loc & 0x01 == 0 {
+            self.page = value;
+        } else {
+            self.bank = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_and_persistence() {
+        let mut georam = GeoRam::new(0x80000).unwrap();
+        georam.write_io2(0xDFFF, 0x1F);
+        georam.write_io2(0xDFFE, 0x3F);
+        georam.write_io1(0xDEFF, 0x42);
+        assert_eq!(georam.ram[0x7FFFF], 0x42);
+        // bank 32 wraps around in 512K
+        georam.write_io2(0xDFFF, 0x20);
+        georam.write_io2(0xDFFE, 0x00);
+        georam.write_io1(0xDE00, 0x43);
+        assert_eq!(georam.ram[0], 0x43);
+
+        let path = std::env::temp_dir().join("georam_pages_and_persistence.bin");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+        assert_eq!(GeoRam::load(path, 0x80000).unwrap().ram[0], 0x00);
+        georam.save(path).unwrap();
+        let restored = GeoRam::load(path, 0x80000).unwrap();
+        assert_eq!(restored.ram[0], 0x43);
+        assert!(GeoRam::load(path, 0x100000).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,43 +1,687 @@
 use crate::emulator::memory::Memory;
 
+const CYCLES_PER_LINE: u16 = 63;
+const LINES_PER_FRAME: u16 = 312;
+const FIRST_BADLINE: u16 = 0x30;
+const LAST_BADLINE: u16 = 0xF7;
+
+/// The X coordinates run from $000 to $1F7, the first pixel of cycle 1 is at $194.
+const PIXELS_PER_LINE: u16 = 504;
+const FIRST_CYCLE_X: u16 = 0x194;
+
+/// The visible area: 32 pixels of border left and right of the 40 columns, 35 lines above and 37
+/// below the 25 rows.
+pub const SCREEN_WIDTH: usize = 384;
+pub const SCREEN_HEIGHT: usize = 272;
+const FIRST_VISIBLE_X: u16 = 0x1F0;
+const FIRST_VISIBLE_LINE: u16 = 16;
+
+/// Where the border opens and closes with CSEL/RSEL set and clear.
+const LEFT_40: u16 = 0x18;
+const LEFT_38: u16 = 0x1F;
+const RIGHT_40: u16 = 0x158;
+const RIGHT_38: u16 = 0x14F;
+const TOP_25: u16 = 0x33;
+const TOP_24: u16 = 0x37;
+const BOTTOM_25: u16 = 0xFB;
+const BOTTOM_24: u16 = 0xF7;
+
+/// The first X coordinate of the graphics with no horizontal scrolling.
+const FIRST_GRAPHICS_X: u16 = 0x18;
+
+/// Sprite display is 24 pixels, or 48 with X expansion, shifted out of the data of one line.
+#[derive(Clone, Copy, Default)]
+struct Sprite {
+    dma: bool,
+    display: bool,
+    /// Counts the 63 bytes of sprite data, MCBASE is its value at the start of the line.
+    mc: u8,
+    mc_base: u8,
+    /// The Y expansion flip-flop, and its value in cycle 15 to tell a sprite crunch.
+    y_expansion: bool,
+    y_expansion_in_15: bool,
+    /// The three bytes of the last s-accesses.
+    data: u32,
+    shift_register: u32,
+    bits_left: u8,
+    /// Holds every other shift with X expansion.
+    x_expansion: bool,
+    /// Toggles with every shift, a new bit pair is latched every other shift.
+    multicolor: bool,
+    pair: u8,
+}
+
+impl Sprite {
+    /// The sequencer starts when the X coordinate matches while the sprite is displayed.
+    /// Returns 0 for transparent, 2 for a hires pixel, or the multicolor bit pair.
+    fn pixel(&mut self, x: u16, sprite_x: u16, multicolor: bool, x_expanded: bool) -> u8 {
+        if x == sprite_x && self.display {
+            self.shift_register = self.data;
+            self.bits_left = 24;
+            self.x_expansion = false;
+            self.multicolor = false;
+            self.pair = ((self.data >> 22) & 0x03) as u8;
+        }
+        if self.bits_left == 0 {
+            return 0;
+        }
+        let pixel = if multicolor { self.pair } else { ((self.shift_register >> 22) & 0x02) as u8 };
+        self.x_expansion = x_expanded && !self.x_expansion;
+        if !self.x_expansion {
+            self.shift_register <<= 1;
+            self.bits_left -= 1;
+            self.multicolor = !self.multicolor;
+            if !self.multicolor {
+                self.pair = ((self.shift_register >> 22) & 0x03) as u8;
+            }
+        }
+        pixel
+    }
+}
+
+/// The VIC-II (6569, PAL) walking the raster beam: 63 cycles per line, 312 lines per frame and
+/// 8 pixels per cycle, of which the visible area ends up in `display`.
 pub struct Gpu {
-    pub current_raster_line: usize,
-    pub x_pos: usize,
-    pub display: [[u8; 320]; 200],
+    pub display: [[u8; SCREEN_WIDTH]; SCREEN_HEIGHT],
+    /// Beam position, the cycle within the line counts from 1 like in the VIC-II documentation.
+    raster_y: u16,
+    raster_cycle: u16,
+    den_in_first_badline: bool,
+    badline: bool,
+    ba_low: bool,
+    /// Display state fetches the text rows, idle state reads $3FFF.
+    display_state: bool,
+    /// Video counter, its value at the start of the row and the row counter.
+    vc: u16,
+    vc_base: u16,
+    rc: u8,
+    vmli: usize,
+    /// The text row read in the last badline.
+    matrix_line: [u8; 40],
+    color_line: [u8; 40],
+    /// What the 40 graphics fetches of the line read, with the video matrix and color RAM bytes
+    /// that select their colors.
+    graphics: [u8; 40],
+    graphics_matrix: [u8; 40],
+    graphics_color: [u8; 40],
+    main_border: bool,
+    vertical_border: bool,
+    sprites: [Sprite; 8],
+    /// Sprite-sprite and sprite-background collisions seen in this cycle.
+    collisions: (u8, u8),
 }
 
 impl Gpu {
     pub fn new() -> Gpu {
         Gpu {
-            current_raster_line: 0,
-            x_pos: 0,
-            display: [[0; 320]; 200],
+            display: [[0; SCREEN_WIDTH]; SCREEN_HEIGHT],
+            raster_y: 0,
+            raster_cycle: 1,
+            den_in_first_badline: false,
+            badline: false,
+            ba_low: false,
+            display_state: false,
+            vc: 0,
+            vc_base: 0,
+            rc: 0,
+            vmli: 0,
+            matrix_line: [0; 40],
+            color_line: [0; 40],
+            graphics: [0; 40],
+            graphics_matrix: [0; 40],
+            graphics_color: [0; 40],
+            main_border: true,
+            vertical_border: true,
+            sprites: [Sprite::default(); 8],
+            collisions: (0, 0),
+        }
+    }
+
+    /// BA is pulled low 3 cycles before the VIC takes over the bus, the CPU stops at its next read.
+    pub fn ba_low(&self) -> bool {
+        self.ba_low
+    }
+
+    /// Runs one cycle: the memory accesses of the cycle, then its 8 pixels.
+    pub fn tick(&mut self, memory: &mut Memory) {
+        // the raster compare happens one cycle later in line 0
+        if self.raster_cycle == if self.raster_y == 0 { 2 } else { 1 } {
+            memory.gpu_mut().set_raster(self.raster_y);
+        }
+        self.cycle(memory);
+        let (sprite_sprite, sprite_background) = std::mem::take(&mut self.collisions);
+        memory.gpu_mut().add_collisions(sprite_sprite, sprite_background);
+    }
+
+    fn cycle(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        if self.raster_y == FIRST_BADLINE && registers.display_enabled() {
+            self.den_in_first_badline = true;
+        }
+        self.update_sprites(memory);
+        self.badline = self.is_badline(memory);
+        self.ba_low = (self.badline && (12..=54).contains(&self.raster_cycle))
+            || (0..8).any(|sprite| self.sprite_dma(sprite));
+        if self.badline {
+            self.display_state = true;
+        }
+
+        match self.raster_cycle {
+            1 if self.raster_y == 0 => self.vc_base = 0,
+            14 => {
+                self.vc = self.vc_base;
+                self.vmli = 0;
+                if self.badline {
+                    self.rc = 0;
+                }
+            }
+            58 => {
+                if self.rc == 7 {
+                    self.vc_base = self.vc;
+                    self.display_state = self.badline;
+                }
+                if self.display_state {
+                    self.rc = (self.rc + 1) & 0x07;
+                }
+            }
+            _ => {}
+        }
+        if (16..=55).contains(&self.raster_cycle) {
+            self.graphics_access(memory);
+        }
+        if self.badline && (15..=54).contains(&self.raster_cycle) {
+            self.matrix_access(memory);
+        }
+        if let Some(sprite) = (0..8).find(|&sprite| sprite_fetch_cycle(sprite) == self.raster_cycle) {
+            self.sprite_access(memory, sprite);
+        }
+
+        self.draw(memory);
+        if self.raster_cycle == CYCLES_PER_LINE {
+            self.update_vertical_border(memory);
+        }
+
+        self.raster_cycle += 1;
+        if self.raster_cycle > CYCLES_PER_LINE {
+            self.raster_cycle = 1;
+            self.raster_y += 1;
+            if self.raster_y == LINES_PER_FRAME {
+                self.raster_y = 0;
+                self.den_in_first_badline = false;
+            }
+        }
+    }
+
+    /// c-access: one character of the next text row and its color.
+    fn matrix_access(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        let bank = memory.cia2().get_vic_bank();
+        let address = bank | registers.get_video_matrix_address() | self.vc;
+        self.matrix_line[self.vmli] = memory.get_from_gpu(address as usize);
+        self.color_line[self.vmli] = memory.color_ram().get(self.vc as usize) & 0x0F;
+    }
+
+    /// g-access: the pixels of one character or 8 pixels of bitmap, or $3FFF in idle state. ECM
+    /// holds address lines 9 and 10 low.
+    fn graphics_access(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        let bank = memory.cia2().get_vic_bank();
+        let column = (self.raster_cycle - 16) as usize;
+        let mut address = if !self.display_state {
+            0x3FFF
+        } else if registers.bitmap_mode() {
+            registers.get_bitmap_address() | (self.vc << 3) | self.rc as u16
+        } else {
+            let character = self.matrix_line[self.vmli] as u16;
+            registers.get_character_bitmap_address() | (character << 3) | self.rc as u16
+        };
+        if registers.extended_color_mode() {
+            address &= 0x39FF;
+        }
+        self.graphics[column] = memory.get_from_gpu((bank | address) as usize);
+        if self.display_state {
+            self.graphics_matrix[column] = self.matrix_line[self.vmli];
+            self.graphics_color[column] = self.color_line[self.vmli];
+            self.vc = (self.vc + 1) & 0x3FF;
+            self.vmli += 1;
+        } else {
+            self.graphics_matrix[column] = 0;
+            self.graphics_color[column] = 0;
         }
     }
 
-    pub fn tick(&mut self, memory: &Memory) {
-        let vic_bank = memory.cia2().get_vic_bank();
-        let matrix_address = memory.gpu().get_video_matrix_address() | vic_bank;
-        let char_address = memory.gpu().get_character_bitmap_address() | vic_bank;
-        if (char_address >= 0x1000 && char_address < 0x2000) || (char_address >= 0x9000 && char_address < 0xA000) {}
+    /// p-access for the sprite pointer at the end of the video matrix, then the three s-accesses
+    /// while the sprite DMA is on.
+    fn sprite_access(&mut self, memory: &Memory, sprite: usize) {
+        let bank = memory.cia2().get_vic_bank();
+        let pointer_address = bank | memory.gpu().get_video_matrix_address() | 0x03F8 | sprite as u16;
+        let pointer = memory.get_from_gpu(pointer_address as usize) as u16;
+        let sprite = &mut self.sprites[sprite];
+        if !sprite.dma {
+            return;
+        }
+        sprite.data = 0;
+        for _ in 0..3 {
+            let address = bank | (pointer << 6) | sprite.mc as u16;
+            sprite.data = (sprite.data << 8) | memory.get_from_gpu(address as usize) as u32;
+            sprite.mc = (sprite.mc + 1) & 0x3F;
+        }
+    }
 
-        let color = memory.color_ram().get((self.current_raster_line >> 3) * 40 + self.x_pos);
-        let character = memory.get_from_gpu(matrix_address as usize + (self.current_raster_line >> 3) * 40 + self.x_pos);
-        let character_slice = memory.get_from_gpu(char_address as usize + character as usize * 8 + (self.current_raster_line & 0x07));
-        for bit in 0usize..8 {
-            self.display[self.current_raster_line][self.x_pos * 8 + 7 - bit] = if (character_slice >> bit) & 0x01 == 0x01 {
-                color
+    /// The sprite DMA and display switch on and off, and the data counters advance, at fixed
+    /// cycles of the line.
+    fn update_sprites(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        for (n, sprite) in self.sprites.iter_mut().enumerate() {
+            // the flip-flop is held set while the expansion is off
+            if !registers.sprite_y_expanded(n) {
+                sprite.y_expansion = true;
+            }
+            match self.raster_cycle {
+                15 => sprite.y_expansion_in_15 = sprite.y_expansion,
+                16 => {
+                    if sprite.y_expansion {
+                        sprite.mc_base = if sprite.y_expansion_in_15 {
+                            sprite.mc
+                        } else {
+                            // the expansion was cleared in cycle 15: sprite crunch
+                            (0x2A & (sprite.mc_base & sprite.mc)) | (0x15 & (sprite.mc_base | sprite.mc))
+                        };
+                    }
+                    if sprite.mc_base == 63 {
+                        sprite.dma = false;
+                    }
+                }
+                55 | 56 => {
+                    if self.raster_cycle == 55 && registers.sprite_y_expanded(n) {
+                        sprite.y_expansion = !sprite.y_expansion;
+                    }
+                    if registers.sprite_enabled(n) && registers.sprite_y(n) == self.raster_y as u8 && !sprite.dma {
+                        sprite.dma = true;
+                        sprite.mc_base = 0;
+                        if registers.sprite_y_expanded(n) {
+                            sprite.y_expansion = false;
+                        }
+                    }
+                }
+                58 => {
+                    sprite.mc = sprite.mc_base;
+                    if sprite.dma && registers.sprite_y(n) == self.raster_y as u8 {
+                        sprite.display = true;
+                    } else if !sprite.dma {
+                        sprite.display = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Runs the sequencers of all sprites for one pixel, the sprite with the lowest number wins.
+    /// Returns its color, whether it is behind the foreground graphics, and all sprites that
+    /// have a pixel here. Every non-transparent sprite pixel counts for collisions.
+    fn sprite_pixel(&mut self, memory: &Memory, x: u16) -> (Option<(u8, bool)>, u8) {
+        if self.sprites.iter().all(|sprite| !sprite.display && sprite.bits_left == 0) {
+            return (None, 0);
+        }
+        let registers = memory.gpu();
+        let mut pixel = None;
+        let mut sprites = 0;
+        for n in (0..8).rev() {
+            let value = self.sprites[n].pixel(x, registers.sprite_x(n), registers.sprite_multicolor(n), registers.sprite_x_expanded(n));
+            if value != 0 {
+                pixel = Some((registers.sprite_pixel_color(n, value), registers.sprite_behind_background(n)));
+                sprites |= 1 << n;
+            }
+        }
+        (pixel, sprites)
+    }
+
+    fn draw(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        let (left, right) = if registers.columns_40() { (LEFT_40, RIGHT_40) } else { (LEFT_38, RIGHT_38) };
+        let first_x = (FIRST_CYCLE_X + (self.raster_cycle - 1) * 8) % PIXELS_PER_LINE;
+        let row = self.raster_y.wrapping_sub(FIRST_VISIBLE_LINE) as usize;
+        for pixel in 0..8 {
+            let x = (first_x + pixel) % PIXELS_PER_LINE;
+            if x == right {
+                self.main_border = true;
+            }
+            if x == left {
+                self.update_vertical_border(memory);
+                if !self.vertical_border {
+                    self.main_border = false;
+                }
+            }
+            // the sprites keep shifting and colliding with each other under the border
+            let (sprite, sprites) = self.sprite_pixel(memory, x);
+            if sprites & sprites.wrapping_sub(1) != 0 {
+                self.collisions.0 |= sprites;
+            }
+            let color = if self.main_border {
+                registers.border_color()
             } else {
-                memory.gpu().background_color()
+                let (color, foreground) = self.graphics_pixel(memory, x);
+                if foreground {
+                    self.collisions.1 |= sprites;
+                }
+                match sprite {
+                    Some((sprite_color, behind)) if !(behind && foreground) => sprite_color,
+                    _ => color,
+                }
             };
-        }
-        self.x_pos += 1;
-        if self.x_pos == 40 {
-            self.x_pos = 0;
-            self.current_raster_line += 1;
-            if self.current_raster_line == 200 {
-                self.current_raster_line = 0;
+            let column = ((x + PIXELS_PER_LINE - FIRST_VISIBLE_X) % PIXELS_PER_LINE) as usize;
+            if row < SCREEN_HEIGHT && column < SCREEN_WIDTH {
+                self.display[row][column] = color;
             }
         }
     }
-}
\ No newline at end of file
+
+    /// The color of the graphics at `x` in the mode selected by ECM, BMM and MCM, and whether it
+    /// is foreground that sprites can be behind and collide with. Of the multicolor bit pairs, 01
+    /// counts as background. The invalid modes are black but still have foreground.
+    fn graphics_pixel(&self, memory: &Memory, x: u16) -> (u8, bool) {
+        let registers = memory.gpu();
+        let offset = x.wrapping_sub(FIRST_GRAPHICS_X + registers.x_scroll() as u16) as usize;
+        if offset >= 320 {
+            return (registers.background_color(0), false);
+        }
+        let column = offset >> 3;
+        let data = self.graphics[column];
+        let matrix = self.graphics_matrix[column];
+        let color = self.graphics_color[column];
+        let (ecm, bmm, mcm) = (registers.extended_color_mode(), registers.bitmap_mode(), registers.multicolor_mode());
+        // multicolor text only for characters with bit 3 of their color set
+        let multicolor = mcm && (bmm || color & 0x08 != 0);
+        // hires pixels are 00 or 10
+        let pixel = if multicolor {
+            (data << (offset & 0x06)) >> 6
+        } else {
+            ((data << (offset & 0x07)) >> 7) << 1
+        };
+        let foreground = pixel & 0x02 != 0;
+        let color = match (ecm, bmm, mcm) {
+            (false, false, true) if multicolor => match pixel {
+                0b11 => color & 0x07,
+                _ => registers.background_color(pixel as usize),
+            },
+            (false, false, _) => if foreground { color } else { registers.background_color(0) },
+            (false, true, false) => if foreground { matrix >> 4 } else { matrix & 0x0F },
+            (false, true, true) => match pixel {
+                0b00 => registers.background_color(0),
+                0b01 => matrix >> 4,
+                0b10 => matrix & 0x0F,
+                _ => color,
+            },
+            (true, false, false) => if foreground { color } else { registers.background_color((matrix >> 6) as usize) },
+            _ => 0x00,
+        };
+        (color, foreground)
+    }
+
+    /// The vertical border closes at the bottom and opens at the top of the display window, in
+    /// the last cycle of the line and where the main border opens.
+    fn update_vertical_border(&mut self, memory: &Memory) {
+        let registers = memory.gpu();
+        let (top, bottom) = if registers.rows_25() { (TOP_25, BOTTOM_25) } else { (TOP_24, BOTTOM_24) };
+        if self.raster_y == bottom {
+            self.vertical_border = true;
+        } else if self.raster_y == top && registers.display_enabled() {
+            self.vertical_border = false;
+        }
+    }
+
+    /// A badline is a line in which the VIC reads the 40 characters of the next text row and keeps
+    /// the CPU off the bus for cycles 15-54.
+    fn is_badline(&self, memory: &Memory) -> bool {
+        (FIRST_BADLINE..=LAST_BADLINE).contains(&self.raster_y)
+            && self.den_in_first_badline
+            && (self.raster_y & 0x07) as u8 == memory.gpu().y_scroll()
+    }
+
+    /// BA goes low 3 cycles before the sprite fetches while its DMA is on.
+    fn sprite_dma(&self, sprite: usize) -> bool {
+        let distance = (self.raster_cycle + CYCLES_PER_LINE - sprite_fetch_cycle(sprite) + 3) % CYCLES_PER_LINE;
+        self.sprites[sprite].dma && distance <= 4
+    }
+}
+
+/// Sprite `n` fetches its pointer and data in cycles 58 + 2n and 59 + 2n, wrapping into the next
+/// line for sprites 3-7.
+fn sprite_fetch_cycle(sprite: usize) -> u16 {
+    (57 + 2 * sprite as u16) % CYCLES_PER_LINE + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::bus::Bus;
+    use crate::emulator::gpu::Gpu;
+    use crate::emulator::memory::Memory;
+    use crate::emulator::roms::Roms;
+
+    fn ba_low_cycles_per_frame(memory: &mut Memory) -> usize {
+        let mut gpu = Gpu::new();
+        (0..63 * 312).filter(|_| {
+            gpu.tick(memory);
+            gpu.ba_low()
+        }).count()
+    }
+
+    #[test]
+    fn bus_available() {
+        let mut memory = Memory::new(Roms::new());
+        memory.write(0xD011, 0x1B);
+        // 25 badlines with BA low from cycle 12 to 54
+        assert_eq!(ba_low_cycles_per_frame(&mut memory), 25 * 43);
+        memory.write(0xD011, 0x0B);
+        assert_eq!(ba_low_cycles_per_frame(&mut memory), 0);
+
+        memory.write(0xD001, 0x80);
+        memory.write(0xD015, 0x01);
+        // cycles 55-59 in each of the 21 lines the sprite is fetched for
+        assert_eq!(ba_low_cycles_per_frame(&mut memory), 21 * 5);
+    }
+
+    fn run_until_irq(gpu: &mut Gpu, memory: &mut Memory) -> u16 {
+        while !memory.gpu().irq_asserted() {
+            gpu.tick(memory);
+        }
+        memory.read(0xD012) as u16 | ((memory.read(0xD011) as u16 & 0x80) << 1)
+    }
+
+    #[test]
+    fn raster_irq() {
+        let mut memory = Memory::new(Roms::new());
+        let mut gpu = Gpu::new();
+        memory.write(0xD012, 0x80);
+        memory.write(0xD01A, 0x01);
+        assert_eq!(run_until_irq(&mut gpu, &mut memory), 0x80);
+        assert_eq!(memory.read(0xD019), 0xF1);
+        memory.write(0xD019, 0x01);
+        assert!(!memory.gpu().irq_asserted(), "writing 1 acknowledges");
+        assert_eq!(memory.read(0xD019), 0x70);
+
+        // bit 8 of the compare value is bit 7 of $D011
+        memory.write(0xD011, 0x9B);
+        memory.write(0xD012, 0x05);
+        assert_eq!(run_until_irq(&mut gpu, &mut memory), 0x105);
+        memory.write(0xD019, 0xFF);
+
+        // masked sources are still latched
+        memory.write(0xD01A, 0x00);
+        for _ in 0..63 * 312 {
+            gpu.tick(&mut memory);
+        }
+        assert_eq!(memory.read(0xD019), 0x71);
+    }
+
+    fn text_screen(memory: &mut Memory, d016: u8) -> Gpu {
+        for (loc, value) in [(0xDD00, 0x03), (0xD011, 0x1B), (0xD016, d016), (0xD018, 0x14), (0xD020, 0x0E), (0xD021, 0x06), (0x0400, 0x01), (0xD800, 0x01)] {
+            memory.write(loc, value);
+        }
+        let mut gpu = Gpu::new();
+        for _ in 0..2 * 63 * 312 {
+            gpu.tick(memory);
+        }
+        gpu
+    }
+
+    #[test]
+    fn border_and_text() {
+        let mut memory = Memory::new(Roms::new());
+        let gpu = text_screen(&mut memory, 0x08);
+        assert_eq!(gpu.display[0][0], 0x0E);
+        // the first text row starts in line $33, 32 pixels into the visible area
+        assert_eq!(gpu.display[34][32], 0x0E);
+        // the top line of "A" is 00011000
+        assert_eq!(&gpu.display[35][31..40], &[0x0E, 0x06, 0x06, 0x06, 0x01, 0x01, 0x06, 0x06, 0x06]);
+        assert_eq!(gpu.display[35][351], 0x06);
+        assert_eq!(gpu.display[35][353], 0x0E, "the border closes after 320 pixels");
+        assert_eq!(gpu.display[234][32], 0x06);
+        assert_eq!(gpu.display[235][32], 0x0E, "25 rows");
+
+        // with 38 columns the border covers the first pixels of the scrolled graphics
+        let gpu = text_screen(&mut memory, 0x03);
+        assert_eq!(&gpu.display[35][38..43], &[0x0E, 0x01, 0x06, 0x06, 0x06]);
+    }
+
+    /// Sprite 0 at X 100, Y 100 with its pointer at $07F8 and data at $0340, on a blank screen.
+    fn sprite_screen(memory: &mut Memory, registers: &[(u16, u8)], data: &[u8]) -> Gpu {
+        for loc in 0x0400..0x07E8 {
+            memory.write(loc, 0x20);
+        }
+        for loc in 0x0340..0x037F {
+            memory.write(loc, data[(loc as usize - 0x0340) % data.len()]);
+        }
+        memory.write(0x07F8, 0x0D);
+        for (loc, value) in [(0xD000, 100), (0xD001, 100), (0xD015, 0x01), (0xD017, 0x00), (0xD01B, 0x00), (0xD01C, 0x00), (0xD01D, 0x00), (0xD016, 0x08), (0xD027, 0x01)].iter().chain(registers) {
+            memory.write(*loc, *value);
+        }
+        let d016 = memory.read(0xD016) & 0x3F;
+        text_screen(memory, d016)
+    }
+
+    #[test]
+    fn sprites() {
+        let mut memory = Memory::new(Roms::new());
+        // X 100 is column 108 and line 101 row 85 of the display
+        let gpu = sprite_screen(&mut memory, &[], &[0x80, 0x00, 0x01]);
+        assert_eq!(&gpu.display[85][107..111], &[0x06, 0x01, 0x06, 0x06]);
+        assert_eq!(&gpu.display[85][130..133], &[0x06, 0x01, 0x06]);
+        assert_eq!(gpu.display[84][108], 0x06);
+        assert_eq!(gpu.display[105][108], 0x01);
+        assert_eq!(gpu.display[106][108], 0x06, "21 lines");
+
+        let gpu = sprite_screen(&mut memory, &[(0xD017, 0x01), (0xD01D, 0x01)], &[0x80, 0x00, 0x01]);
+        assert_eq!(&gpu.display[85][107..111], &[0x06, 0x01, 0x01, 0x06]);
+        assert_eq!(&gpu.display[85][153..156], &[0x06, 0x01, 0x01]);
+        assert_eq!(gpu.display[126][108], 0x01);
+        assert_eq!(gpu.display[127][108], 0x06, "42 lines");
+
+        // bit pairs 11, 10, 01, 00
+        let gpu = sprite_screen(&mut memory, &[(0xD01C, 0x01), (0xD025, 0x02), (0xD026, 0x03)], &[0xE4, 0x00, 0x00]);
+        assert_eq!(&gpu.display[85][108..116], &[0x03, 0x03, 0x01, 0x01, 0x02, 0x02, 0x06, 0x06]);
+
+        // sprite 1 on top of sprite 0 loses, sprite 0 in the left border is covered
+        let gpu = sprite_screen(&mut memory, &[(0xD000, 0x10), (0xD002, 0x10), (0xD003, 100), (0xD015, 0x03), (0xD028, 0x05), (0x07F9, 0x0D)], &[0xFF, 0xFF, 0xFF]);
+        assert_eq!(&gpu.display[85][30..34], &[0x0E, 0x0E, 0x01, 0x01]);
+
+        // behind the foreground of the text, in front of the background
+        let gpu = sprite_screen(&mut memory, &[(0xD000, 0x30), (0xD001, 0x32), (0xD01B, 0x01), (0x0403, 0x01), (0xD803, 0x00)], &[0xFF, 0xFF, 0xFF]);
+        assert_eq!(&gpu.display[35][56..64], &[0x01, 0x01, 0x01, 0x00, 0x00, 0x01, 0x01, 0x01]);
+    }
+
+    #[test]
+    fn sprite_crunch() {
+        let mut memory = Memory::new(Roms::new());
+        for (loc, value) in [(0xD001, 100), (0xD015, 0x01), (0xD017, 0x01)] {
+            memory.write(loc, value);
+        }
+        let mut gpu = Gpu::new();
+        while !(gpu.raster_y == 103 && gpu.raster_cycle == 16) {
+            gpu.tick(&mut memory);
+        }
+        // clearing the expansion in cycle 15 of a line that repeats the data
+        memory.write(0xD017, 0x00);
+        gpu.tick(&mut memory);
+        assert_eq!(gpu.sprites[0].mc_base, 7);
+        // MCBASE wraps around and the DMA runs past the end of the sprite
+        while gpu.sprites[0].dma {
+            gpu.tick(&mut memory);
+        }
+        assert_eq!(gpu.raster_y, 103 + 19 + 21);
+    }
+
+    #[test]
+    fn collisions() {
+        let mut memory = Memory::new(Roms::new());
+        // sprites 0 and 1 on top of each other in the left border
+        sprite_screen(&mut memory, &[(0xD000, 0x10), (0xD002, 0x10), (0xD003, 100), (0xD015, 0x03), (0x07F9, 0x0D)], &[0xFF, 0xFF, 0xFF]);
+        assert_eq!(memory.read(0xD01E), 0x03);
+        assert_eq!(memory.read(0xD01E), 0x00, "cleared when read");
+        assert_eq!(memory.read(0xD01F), 0x00);
+        assert_eq!(memory.read(0xD019) & 0x06, 0x04);
+        memory.write(0xD019, 0x04);
+
+        // one line of sprite on the pixels 2 and 3 of the top line of "A", 00011000
+        let mut data = [0x00; 63];
+        data[0] = 0x30;
+        let registers = [(0xD000, 0x30), (0xD001, 0x32), (0x0403, 0x01), (0xD803, 0x09), (0xD022, 0x02), (0xD023, 0x03)];
+        sprite_screen(&mut memory, &registers, &data);
+        assert_eq!(memory.read(0xD01F), 0x01);
+        assert_eq!(memory.read(0xD019) & 0x06, 0x02);
+
+        // in multicolor they are the pair 01, which counts as background
+        let multicolor = [&registers[..], &[(0xD016, 0x18)]].concat();
+        let gpu = sprite_screen(&mut memory, &multicolor, &data);
+        assert_eq!(&gpu.display[35][56..64], &[0x06, 0x06, 0x01, 0x01, 0x03, 0x03, 0x06, 0x06]);
+        assert_eq!(memory.read(0xD01F), 0x00);
+        data[0] = 0x0C;
+        sprite_screen(&mut memory, &multicolor, &data);
+        assert_eq!(memory.read(0xD01F), 0x01);
+    }
+
+    fn mode_screen(memory: &mut Memory, d011: u8, d016: u8, d018: u8) -> Gpu {
+        for (loc, value) in [(0xDD00, 0x03), (0xD011, d011), (0xD016, d016), (0xD018, d018), (0xD020, 0x0E), (0xD021, 0x06), (0xD022, 0x02), (0xD023, 0x03), (0xD024, 0x04)] {
+            memory.write(loc, value);
+        }
+        let mut gpu = Gpu::new();
+        for _ in 0..2 * 63 * 312 {
+            gpu.tick(memory);
+        }
+        gpu
+    }
+
+    #[test]
+    fn graphics_modes() {
+        let mut memory = Memory::new(Roms::new());
+        // "A" with background color 3
+        memory.write(0x0400, 0xC1);
+        memory.write(0xD800, 0x01);
+        let gpu = mode_screen(&mut memory, 0x5B, 0x08, 0x14);
+        assert_eq!(&gpu.display[35][32..40], &[0x04, 0x04, 0x04, 0x01, 0x01, 0x04, 0x04, 0x04]);
+
+        memory.write(0x0400, 0x52);
+        memory.write(0xD800, 0x07);
+        memory.write(0x2000, 0x1B);
+        let gpu = mode_screen(&mut memory, 0x3B, 0x08, 0x18);
+        assert_eq!(&gpu.display[35][32..40], &[0x02, 0x02, 0x02, 0x05, 0x05, 0x02, 0x05, 0x05]);
+        let gpu = mode_screen(&mut memory, 0x3B, 0x18, 0x18);
+        assert_eq!(&gpu.display[35][32..40], &[0x06, 0x06, 0x05, 0x05, 0x02, 0x02, 0x07, 0x07]);
+
+        for d011 in [0x5B, 0x7B] {
+            let gpu = mode_screen(&mut memory, d011, 0x18, 0x18);
+            assert_eq!(&gpu.display[35][31..40], &[0x0E, 0, 0, 0, 0, 0, 0, 0, 0], "invalid modes are black");
+        }
+        let gpu = mode_screen(&mut memory, 0x7B, 0x08, 0x18);
+        assert_eq!(&gpu.display[35][32..40], &[0; 8]);
+
+        // with YSCROLL 0 the lines after the last text row are idle, ECM reads $39FF there
+        memory.write(0x3FFF, 0x00);
+        memory.write(0x39FF, 0xFF);
+        let gpu = mode_screen(&mut memory, 0x18, 0x08, 0x14);
+        assert_eq!(gpu.display[0xF8 - 16][40], 0x06);
+        let gpu = mode_screen(&mut memory, 0x58, 0x08, 0x14);
+        assert_eq!(gpu.display[0xF8 - 16][40], 0x00);
+    }
+}
@@ -0,0 +1,58 @@
+/// Devices that can pull the open collector IRQ line of the CPU low.
+#[derive(Clone, Copy)]
+pub enum IrqSource {
+    Vic,
+    Cia1,
+    ExpansionPort,
+}
+
+impl IrqSource {
+    fn mask(self) -> u8 {
+        match self {
+            IrqSource::Vic => 0x01,
+            IrqSource::Cia1 => 0x02,
+            IrqSource::ExpansionPort => 0x04,
+        }
+    }
+}
+
+/// The IRQ line is a wired-OR, it stays asserted as long as at least one source holds it.
+pub struct IrqLine {
+    sources: u8,
+}
+
+impl IrqLine {
+    pub fn new() -> IrqLine {
+        IrqLine {
+            sources: 0,
+        }
+    }
+
+    pub fn set(&mut self, source: IrqSource, asserted: bool) {
+        if asserted {
+            self.sources |= source.mask();
+        } else {
+            self.sources &= !source.mask();
+        }
+    }
+
+    pub fn asserted(&self) -> bool {
+        self.sources != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wired_or() {
+        let mut line = IrqLine::new();
+        line.set(IrqSource::Cia1, true);
+        line.set(IrqSource::Vic, true);
+        line.set(IrqSource::Cia1, false);
+        assert!(line.asserted());
+        line.set(IrqSource::Vic, false);
+        assert!(!line.asserted());
+    }
+}
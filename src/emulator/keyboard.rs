@@ -69,6 +69,7 @@ pub enum Key {
 
 pub struct Keyboard {
     pressed: [bool; 64],
+    restore: bool,
     // col: u8,
     // row: u8,
 }
@@ -77,6 +78,7 @@ impl Keyboard {
     pub fn new() -> Keyboard {
         Keyboard {
             pressed: [false; 64],
+            restore: false,
             // col: 0,
             // row: 0,
         }
@@ -84,7 +86,11 @@ impl Keyboard {
 
     pub fn change_key_state(&mut self, key: Key, pressed: bool) {
         // let a = ;
-        self.pressed[key as usize] = pressed;
+        match key {
+            // RESTORE is not part of the matrix, it is wired to the NMI line
+            Key::Restore => self.restore = pressed,
+            key => self.pressed[key as usize] = pressed,
+        }
         // self.col = 0;
         // self.row = 0;
         // for (i, pressed) in self.pressed.iter().enumerate() {
@@ -100,6 +106,10 @@ impl Keyboard {
     pub fn pressed(&self) -> [bool; 64] {
         self.pressed
     }
+
+    pub fn restore_pressed(&self) -> bool {
+        self.restore
+    }
 }
 
 #[cfg(test)]
@@ -2,6 +2,7 @@ use log::debug;
 
 use crate::emulator::addressing::Addressing;
 use crate::emulator::cpu::Cpu;
+use crate::emulator::opcodes::{disassemble, OPCODES};
 
 pub struct CpuLogger {
     enabled: bool,
@@ -67,15 +68,18 @@ impl CpuLogger {
         let mut line = String::new();
         line.push_str(format!("({:4})[sp={:02X},p={:02X},a={:02X},x={:02X},y={:02X}] {:04X}   {:02X}", self.tick, self.sp, self.p, self.a, self.x, self.y, self.pc, self.opcode).as_str());
         // line.push_str(format!("{:04X}   {:02X}", self.pc, self.opcode).as_str());
-        line.push_str(match addressing.operand1() {
-            Some(x) => format!(" {:02X}", x),
-            None => "   ".to_string()
-        }.as_str());
-        line.push_str(match addressing.operand2() {
-            Some(x) => format!(" {:02X}", x),
-            None => "   ".to_string()
-        }.as_str());
-        line.push_str(format!("   {} {}", cpu.inst, addressing.description()).as_str());
+        if cpu.interrupted_started {
+            line.push_str(format!("         {}", cpu.inst).as_str());
+        } else {
+            let bytes = [self.opcode, addressing.operand1().unwrap_or(0), addressing.operand2().unwrap_or(0)];
+            for (i, byte) in bytes.iter().enumerate().skip(1) {
+                line.push_str(match i < OPCODES[self.opcode as usize].bytes as usize {
+                    true => format!(" {:02X}", byte),
+                    false => "   ".to_string()
+                }.as_str());
+            }
+            line.push_str(format!("   {}", disassemble(self.pc, bytes)).as_str());
+        }
 
         if self.disabled && self.pc == self.disabled_until {
             self.disabled = false;
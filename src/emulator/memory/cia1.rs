@@ -1,3 +1,5 @@
+use crate::emulator::timer_a::TimerARegisters;
+
 pub struct Cia1 {
     port_a_write: u8,
     port_b_read: u8,
@@ -58,6 +60,9 @@ impl Cia1 {
                 } else {
                     self.interrupt_mask &= !value & 0x1F;
                 }
+                if self.interrupt_data & self.interrupt_mask != 0 {
+                    self.interrupt_data |= 0x80;
+                }
             }
             0xDC0E => self.timer_a_control = value,
             0xDC0F => self.timer_b_control = value,
@@ -65,24 +70,9 @@ impl Cia1 {
         }
     }
 
-    pub fn timer_a_start_value_low(&self) -> u8 {
-        self.timer_a_start_value_low
-    }
-
-    pub fn timer_a_start_value_high(&self) -> u8 {
-        self.timer_a_start_value_high
-    }
-
-    pub fn interrupt_timer_a(&mut self) -> bool {
-        if self.interrupt_mask & 0x01 == 0x01 {
-            self.interrupt_data = 0x81;
-            return true;
-        }
-        false
-    }
-
-    pub fn timer_a_control(&self) -> u8 {
-        self.timer_a_control
+    /// The interrupt output stays asserted until $DC0D is read.
+    pub fn irq_asserted(&self) -> bool {
+        self.interrupt_data & 0x80 == 0x80
     }
 
     pub fn port_b_read_or(&mut self, value: u8) {
@@ -110,4 +100,29 @@ impl Cia1 {
     // pub fn port_b_read(&self) -> u8 {
     //     self.port_b_read
     // }
+}
+
+impl TimerARegisters for Cia1 {
+    fn timer_a_start_value_low(&self) -> u8 {
+        self.timer_a_start_value_low
+    }
+
+    fn timer_a_start_value_high(&self) -> u8 {
+        self.timer_a_start_value_high
+    }
+
+    fn timer_a_control(&self) -> u8 {
+        self.timer_a_control
+    }
+
+    fn stop_timer_a(&mut self) {
+        self.timer_a_control &= !0x01;
+    }
+
+    fn interrupt_timer_a(&mut self) {
+        self.interrupt_data |= 0x01;
+        if self.interrupt_mask & 0x01 == 0x01 {
+            self.interrupt_data |= 0x80;
+        }
+    }
 }
\ No newline at end of file
@@ -1,31 +1,134 @@
+use crate::emulator::timer_a::TimerARegisters;
+
 pub struct Cia2 {
     port_a: u8,
+    timer_a_start_value_low: u8,
+    timer_a_start_value_high: u8,
+    interrupt_mask: u8,
+    interrupt_data: u8,
+    timer_a_control: u8,
 }
 
 impl Cia2 {
     pub fn new() -> Cia2 {
         Cia2 {
             port_a: 0,
+            timer_a_start_value_low: 0,
+            timer_a_start_value_high: 0,
+            interrupt_mask: 0,
+            interrupt_data: 0,
+            timer_a_control: 0,
         }
     }
 
-    pub fn get(&self, loc: usize) -> u8 {
+    pub fn get(&mut self, loc: usize) -> u8 {
         if loc == 0xDD00 {
             return self.port_a;
         }
+        if loc == 0xDD0D {
+            let i = self.interrupt_data;
+            self.interrupt_data = 0;
+            return i;
+        }
+        if loc == 0xDD0E {
+            return self.timer_a_control;
+        }
         //debug!("cia2 get {:04X} ", loc);
         0
     }
 
     pub fn set(&mut self, loc: usize, value: u8) {
-        if loc == 0xDD00 {
-            self.port_a = value;
-        } else {
-            //debug!("cia2 set {:04X} = {:02X}", loc, value);
+        match loc {
+            0xDD00 => self.port_a = value,
+            0xDD04 => self.timer_a_start_value_low = value,
+            0xDD05 => self.timer_a_start_value_high = value,
+            0xDD0D => {
+                if value & 0x80 == 0x80 {
+                    self.interrupt_mask |= value & 0x1F;
+                } else {
+                    self.interrupt_mask &= !value & 0x1F;
+                }
+                if self.interrupt_data & self.interrupt_mask != 0 {
+                    self.interrupt_data |= 0x80;
+                }
+            }
+            0xDD0E => self.timer_a_control = value,
+            _ => {
+                //debug!("cia2 set {:04X} = {:02X}", loc, value);
+            }
         }
     }
 
     pub fn get_vic_bank(&self) -> u16 {
         (!((self.port_a as u16) << 14)) & 0xC000
     }
-}
\ No newline at end of file
+
+    /// The CIA2 interrupt output is wired to the NMI line and stays asserted until $DD0D is read.
+    pub fn nmi_asserted(&self) -> bool {
+        self.interrupt_data & 0x80 == 0x80
+    }
+}
+
+impl TimerARegisters for Cia2 {
+    fn timer_a_start_value_low(&self) -> u8 {
+        self.timer_a_start_value_low
+    }
+
+    fn timer_a_start_value_high(&self) -> u8 {
+        self.timer_a_start_value_high
+    }
+
+    fn timer_a_control(&self) -> u8 {
+        self.timer_a_control
+    }
+
+    fn stop_timer_a(&mut self) {
+        self.timer_a_control &= !0x01;
+    }
+
+    fn interrupt_timer_a(&mut self) {
+        self.interrupt_data |= 0x01;
+        if self.interrupt_mask & 0x01 == 0x01 {
+            self.interrupt_data |= 0x80;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::memory::cia2::Cia2;
+    use crate::emulator::timer_a::{TimerA, TimerARegisters};
+
+    #[test]
+    fn interrupt_control_register() {
+        let mut cia2 = Cia2::new();
+        // a masked source only sets its flag
+        cia2.interrupt_timer_a();
+        assert!(!cia2.nmi_asserted());
+        // enabling it while the flag is set asserts the line
+        cia2.set(0xDD0D, 0x81);
+        assert!(cia2.nmi_asserted());
+        // reading clears the flags and releases the line, so the next underflow is a new edge
+        assert_eq!(cia2.get(0xDD0D), 0x81);
+        assert!(!cia2.nmi_asserted());
+        assert_eq!(cia2.get(0xDD0D), 0x00);
+        cia2.interrupt_timer_a();
+        assert!(cia2.nmi_asserted());
+    }
+
+    #[test]
+    fn timer_a_with_pb6_output() {
+        let mut cia2 = Cia2::new();
+        let mut timer_a = TimerA::new();
+        cia2.set(0xDD04, 0x02);
+        cia2.set(0xDD0D, 0x81);
+        // started with PB6 in toggle mode, force loaded
+        cia2.set(0xDD0E, 0x17);
+        for _ in 0..2 {
+            timer_a.tick(&mut cia2);
+        }
+        assert!(!cia2.nmi_asserted());
+        timer_a.tick(&mut cia2);
+        assert!(cia2.nmi_asserted());
+    }
+}
@@ -1,42 +1,200 @@
+/// The sources in $D019 and $D01A.
+pub const IRQ_RASTER: u8 = 0x01;
+pub const IRQ_SPRITE_BACKGROUND: u8 = 0x02;
+pub const IRQ_SPRITE_SPRITE: u8 = 0x04;
+
 pub struct Gpu {
+    sprite_x: [u8; 8],
+    sprite_y: [u8; 8],
+    /// Bit 8 of the X coordinates.
+    sprite_x_msb: u8,
+    control_register_1: u8,
+    /// Bit 8 comes from bit 7 of $D011.
+    raster_compare: u16,
+    /// The line the VIC is in, as read from $D012 and bit 7 of $D011.
+    raster: u16,
+    irq_latch: u8,
+    irq_mask: u8,
+    sprite_enable: u8,
+    control_register_2: u8,
+    sprite_y_expansion: u8,
     memory_control_register: u8,
     border_color: u8,
-    background_color: u8,
+    /// $D021-$D024, the extra colors of the multicolor and extended background modes.
+    background_color: [u8; 4],
+    /// Sprites behind the foreground graphics.
+    sprite_priority: u8,
+    sprite_multicolor: u8,
+    sprite_x_expansion: u8,
+    sprite_multicolor_0: u8,
+    sprite_multicolor_1: u8,
+    sprite_color: [u8; 8],
+    /// The collision latches, cleared when read.
+    sprite_sprite_collision: u8,
+    sprite_background_collision: u8,
 }
 
 impl Gpu {
     pub fn new() -> Gpu {
         Gpu {
+            sprite_x: [0; 8],
+            sprite_y: [0; 8],
+            sprite_x_msb: 0,
+            control_register_1: 0,
+            raster_compare: 0,
+            raster: 0,
+            irq_latch: 0,
+            irq_mask: 0,
+            sprite_enable: 0,
+            control_register_2: 0,
+            sprite_y_expansion: 0,
             memory_control_register: 0,
             border_color: 0,
-            background_color: 0,
+            background_color: [0; 4],
+            sprite_priority: 0,
+            sprite_multicolor: 0,
+            sprite_x_expansion: 0,
+            sprite_multicolor_0: 0,
+            sprite_multicolor_1: 0,
+            sprite_color: [0; 8],
+            sprite_sprite_collision: 0,
+            sprite_background_collision: 0,
         }
     }
 
-    pub fn get(&self, loc: usize) -> u8 {
-        if loc == 0xD018 {
+    pub fn get(&mut self, loc: usize) -> u8 {
+        if (0xD000..=0xD00E).contains(&loc) && loc & 0x01 == 0x00 {
+            return self.sprite_x[(loc - 0xD000) >> 1];
+        } else if (0xD001..=0xD00F).contains(&loc) && loc & 0x01 == 0x01 {
+            return self.sprite_y[(loc - 0xD001) >> 1];
+        } else if loc == 0xD010 {
+            return self.sprite_x_msb;
+        } else if loc == 0xD011 {
+            return (self.control_register_1 & 0x7F) | ((self.raster >> 1) as u8 & 0x80);
+        } else if loc == 0xD012 {
+            return self.raster as u8;
+        } else if loc == 0xD015 {
+            return self.sprite_enable;
+        } else if loc == 0xD016 {
+            return self.control_register_2 | 0xC0;
+        } else if loc == 0xD017 {
+            return self.sprite_y_expansion;
+        } else if loc == 0xD018 {
             return self.memory_control_register;
+        } else if loc == 0xD019 {
+            let irq = if self.irq_asserted() { 0x80 } else { 0x00 };
+            return self.irq_latch | irq | 0x70;
+        } else if loc == 0xD01A {
+            return self.irq_mask | 0xF0;
+        } else if loc == 0xD01B {
+            return self.sprite_priority;
+        } else if loc == 0xD01C {
+            return self.sprite_multicolor;
+        } else if loc == 0xD01D {
+            return self.sprite_x_expansion;
+        } else if loc == 0xD01E {
+            return std::mem::take(&mut self.sprite_sprite_collision);
+        } else if loc == 0xD01F {
+            return std::mem::take(&mut self.sprite_background_collision);
         } else if loc == 0xD020 {
             return self.border_color;
-        } else if loc == 0xD021 {
-            return self.background_color;
+        } else if (0xD021..=0xD024).contains(&loc) {
+            return self.background_color[loc - 0xD021];
+        } else if loc == 0xD025 {
+            return self.sprite_multicolor_0 | 0xF0;
+        } else if loc == 0xD026 {
+            return self.sprite_multicolor_1 | 0xF0;
+        } else if (0xD027..=0xD02E).contains(&loc) {
+            return self.sprite_color[loc - 0xD027] | 0xF0;
         }
         //debug!("gpu get {:04X} ", loc);
         0
     }
 
     pub fn set(&mut self, loc: usize, value: u8) {
-        if loc == 0xD018 {
+        if (0xD000..=0xD00E).contains(&loc) && loc & 0x01 == 0x00 {
+            self.sprite_x[(loc - 0xD000) >> 1] = value;
+        } else if (0xD001..=0xD00F).contains(&loc) && loc & 0x01 == 0x01 {
+            self.sprite_y[(loc - 0xD001) >> 1] = value;
+        } else if loc == 0xD010 {
+            self.sprite_x_msb = value;
+        } else if loc == 0xD011 {
+            self.control_register_1 = value;
+            self.set_raster_compare((self.raster_compare & 0x00FF) | ((value as u16 & 0x80) << 1));
+        } else if loc == 0xD012 {
+            self.set_raster_compare((self.raster_compare & 0x0100) | value as u16);
+        } else if loc == 0xD015 {
+            self.sprite_enable = value;
+        } else if loc == 0xD016 {
+            self.control_register_2 = value;
+        } else if loc == 0xD017 {
+            self.sprite_y_expansion = value;
+        } else if loc == 0xD018 {
             self.memory_control_register = value;
+        } else if loc == 0xD019 {
+            // writing 1s acknowledges
+            self.irq_latch &= !value & 0x0F;
+        } else if loc == 0xD01A {
+            self.irq_mask = value & 0x0F;
+        } else if loc == 0xD01B {
+            self.sprite_priority = value;
+        } else if loc == 0xD01C {
+            self.sprite_multicolor = value;
+        } else if loc == 0xD01D {
+            self.sprite_x_expansion = value;
         } else if loc == 0xD020 {
             self.border_color = value;
-        } else if loc == 0xD021 {
-            self.background_color = value;
+        } else if (0xD021..=0xD024).contains(&loc) {
+            self.background_color[loc - 0xD021] = value;
+        } else if loc == 0xD025 {
+            self.sprite_multicolor_0 = value & 0x0F;
+        } else if loc == 0xD026 {
+            self.sprite_multicolor_1 = value & 0x0F;
+        } else if (0xD027..=0xD02E).contains(&loc) {
+            self.sprite_color[loc - 0xD027] = value & 0x0F;
         } else {
             //debug!("gpu set {:04X} = {:02X}", loc, value);
         }
     }
 
+    /// Called by the VIC when it starts a new line.
+    pub fn set_raster(&mut self, raster: u16) {
+        self.raster = raster;
+        if raster == self.raster_compare {
+            self.trigger_irq(IRQ_RASTER);
+        }
+    }
+
+    /// Changing the compare value to the current line triggers right away.
+    fn set_raster_compare(&mut self, raster_compare: u16) {
+        if raster_compare != self.raster_compare && raster_compare == self.raster {
+            self.trigger_irq(IRQ_RASTER);
+        }
+        self.raster_compare = raster_compare;
+    }
+
+    /// The collisions the VIC saw in a cycle. Only the first collision after the latch was read
+    /// raises an interrupt.
+    pub fn add_collisions(&mut self, sprite_sprite: u8, sprite_background: u8) {
+        if sprite_sprite != 0 && self.sprite_sprite_collision == 0 {
+            self.trigger_irq(IRQ_SPRITE_SPRITE);
+        }
+        if sprite_background != 0 && self.sprite_background_collision == 0 {
+            self.trigger_irq(IRQ_SPRITE_BACKGROUND);
+        }
+        self.sprite_sprite_collision |= sprite_sprite;
+        self.sprite_background_collision |= sprite_background;
+    }
+
+    fn trigger_irq(&mut self, source: u8) {
+        self.irq_latch |= source;
+    }
+
+    /// The IRQ line is held while a latched source is enabled in the mask.
+    pub fn irq_asserted(&self) -> bool {
+        self.irq_latch & self.irq_mask != 0
+    }
+
     pub fn get_video_matrix_address(&self) -> u16 {
         ((self.memory_control_register as u16) << 6) & 0x3C00
     }
@@ -45,7 +203,90 @@ impl Gpu {
         ((self.memory_control_register as u16) << 10) & 0x3C00
     }
 
-    pub fn background_color(&self) -> u8 {
-        self.background_color
+    /// CB13, the bitmap is in the lower or upper 8K of the bank.
+    pub fn get_bitmap_address(&self) -> u16 {
+        ((self.memory_control_register as u16) << 10) & 0x2000
+    }
+
+    pub fn background_color(&self, n: usize) -> u8 {
+        self.background_color[n] & 0x0F
+    }
+
+    pub fn border_color(&self) -> u8 {
+        self.border_color & 0x0F
+    }
+
+    pub fn y_scroll(&self) -> u8 {
+        self.control_register_1 & 0x07
+    }
+
+    pub fn display_enabled(&self) -> bool {
+        self.control_register_1 & 0x10 == 0x10
+    }
+
+    /// ECM, the upper two bits of a character select one of four background colors.
+    pub fn extended_color_mode(&self) -> bool {
+        self.control_register_1 & 0x40 == 0x40
+    }
+
+    /// BMM, bitmap instead of text.
+    pub fn bitmap_mode(&self) -> bool {
+        self.control_register_1 & 0x20 == 0x20
     }
-}
\ No newline at end of file
+
+    /// RSEL, 25 rows instead of 24.
+    pub fn rows_25(&self) -> bool {
+        self.control_register_1 & 0x08 == 0x08
+    }
+
+    pub fn x_scroll(&self) -> u8 {
+        self.control_register_2 & 0x07
+    }
+
+    /// MCM, multicolor text and bitmap.
+    pub fn multicolor_mode(&self) -> bool {
+        self.control_register_2 & 0x10 == 0x10
+    }
+
+    /// CSEL, 40 columns instead of 38.
+    pub fn columns_40(&self) -> bool {
+        self.control_register_2 & 0x08 == 0x08
+    }
+
+    pub fn sprite_x(&self, sprite: usize) -> u16 {
+        self.sprite_x[sprite] as u16 | (((self.sprite_x_msb >> sprite) & 0x01) as u16) << 8
+    }
+
+    pub fn sprite_y(&self, sprite: usize) -> u8 {
+        self.sprite_y[sprite]
+    }
+
+    pub fn sprite_enabled(&self, sprite: usize) -> bool {
+        self.sprite_enable & (1 << sprite) != 0
+    }
+
+    pub fn sprite_y_expanded(&self, sprite: usize) -> bool {
+        self.sprite_y_expansion & (1 << sprite) != 0
+    }
+
+    pub fn sprite_behind_background(&self, sprite: usize) -> bool {
+        self.sprite_priority & (1 << sprite) != 0
+    }
+
+    pub fn sprite_multicolor(&self, sprite: usize) -> bool {
+        self.sprite_multicolor & (1 << sprite) != 0
+    }
+
+    pub fn sprite_x_expanded(&self, sprite: usize) -> bool {
+        self.sprite_x_expansion & (1 << sprite) != 0
+    }
+
+    /// The color of a sprite pixel: 1 and 3 are the shared multicolors, 2 the sprite color.
+    pub fn sprite_pixel_color(&self, sprite: usize, pixel: u8) -> u8 {
+        match pixel {
+            1 => self.sprite_multicolor_0,
+            3 => self.sprite_multicolor_1,
+            _ => self.sprite_color[sprite],
+        }
+    }
+}
@@ -0,0 +1,130 @@
+/// Cycles until a bit 6/7 that is switched to input reads back as 0 (about 350ms on a PAL C64).
+const FALL_OFF_CYCLES: u32 = 350_000;
+
+/// The 6510 on-chip I/O port at $00 (data direction) and $01 (data).
+///
+/// Bits 0-2 (LORAM, HIRAM, CHAREN) and bit 4 (cassette sense) are pulled up, bit 5 (cassette
+/// motor) reads 0 when it is an input. Bits 6 and 7 are not connected, when switched to input
+/// they keep the last written value on the pin capacitance until it discharges.
+pub struct IoPort {
+    direction: u8,
+    data: u8,
+    cassette_button_pressed: bool,
+    fall_off_value: u8,
+    fall_off_cycles: [u32; 2],
+}
+
+impl IoPort {
+    pub fn new() -> IoPort {
+        IoPort {
+            direction: 0x2F,
+            data: 0x37,
+            cassette_button_pressed: false,
+            fall_off_value: 0,
+            fall_off_cycles: [0; 2],
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for bit in 0..2 {
+            if self.fall_off_cycles[bit] > 0 {
+                self.fall_off_cycles[bit] -= 1;
+                if self.fall_off_cycles[bit] == 0 {
+                    self.fall_off_value &= !(0x40 << bit);
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, loc: usize) -> u8 {
+        if loc == 0x0000 {
+            return self.direction;
+        }
+        let mut input = 0x17 | (self.data & 0x08);
+        if self.cassette_button_pressed {
+            input &= !0x10;
+        }
+        input |= self.fall_off_value & 0xC0;
+        (self.data & self.direction) | (input & !self.direction)
+    }
+
+    pub fn set(&mut self, loc: usize, value: u8) {
+        if loc == 0x0000 {
+            for bit in 0..2 {
+                let mask = 0x40 << bit;
+                if self.direction & mask != 0 && value & mask == 0 {
+                    self.fall_off_value = (self.fall_off_value & !mask) | (self.data & mask);
+                    self.fall_off_cycles[bit] = FALL_OFF_CYCLES;
+                }
+            }
+            self.direction = value;
+        } else {
+            self.data = value;
+            for bit in 0..2 {
+                let mask = 0x40 << bit;
+                if self.direction & mask != 0 {
+                    self.fall_off_value = (self.fall_off_value & !mask) | (value & mask);
+                }
+            }
+        }
+    }
+
+    /// LORAM, HIRAM and CHAREN as seen by the PLA, inputs are pulled up.
+    pub fn memory_configuration(&self) -> u8 {
+        (self.data | !self.direction) & 0x07
+    }
+
+    #[cfg(test)]
+    pub fn set_cassette_button_pressed(&mut self, pressed: bool) {
+        self.cassette_button_pressed = pressed;
+    }
+
+    #[cfg(test)]
+    pub fn cassette_write(&self) -> bool {
+        (self.data | !self.direction) & 0x08 == 0x08
+    }
+
+    /// The motor is on while bit 5 is an output driven low.
+    #[cfg(test)]
+    pub fn cassette_motor(&self) -> bool {
+        self.direction & 0x20 == 0x20 && self.data & 0x20 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::emulator::memory::io_port::{FALL_OFF_CYCLES, IoPort};
+
+    #[test]
+    fn inputs_and_fall_off() {
+        let mut port = IoPort::new();
+        port.set(0x0000, 0xFF);
+        port.set(0x0001, 0xC0);
+        port.set(0x0000, 0x00);
+        // bits 0-2 and 4 pulled up, motor reads 0, 6 and 7 still charged
+        assert_eq!(port.get(0x0001), 0xD7);
+        assert_eq!(port.memory_configuration(), 0x07);
+        port.set_cassette_button_pressed(true);
+        assert_eq!(port.get(0x0001), 0xC7);
+        for _ in 0..FALL_OFF_CYCLES {
+            port.tick();
+        }
+        assert_eq!(port.get(0x0001), 0x07);
+    }
+
+    #[test]
+    fn cassette_lines() {
+        let mut port = IoPort::new();
+        // the KERNAL default $2F/$37: motor off, write line low
+        assert!(!port.cassette_motor());
+        assert!(!port.cassette_write());
+        port.set(0x0001, 0x0F);
+        assert!(port.cassette_motor());
+        assert!(port.cassette_write());
+        // as inputs the write line is pulled up and the motor stays off
+        port.set(0x0001, 0x07);
+        port.set(0x0000, 0x07);
+        assert!(!port.cassette_motor());
+        assert!(port.cassette_write());
+    }
+}
\ No newline at end of file
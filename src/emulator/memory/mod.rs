@@ -1,19 +1,34 @@
+use std::any::Any;
+
+use crate::emulator::bus::Bus;
+use crate::emulator::expansion_port::ExpansionPort;
 use crate::emulator::memory::cia1::Cia1;
 use crate::emulator::memory::cia2::Cia2;
 use crate::emulator::memory::color_ram::ColorRAM;
 use crate::emulator::memory::gpu::Gpu;
+use crate::emulator::memory::io_port::IoPort;
+use crate::emulator::memory::pla::{Bank, Pla};
+use crate::emulator::roms::Roms;
 
 mod color_ram;
-mod cia2;
+pub mod cia2;
 mod gpu;
+mod io_port;
+mod pla;
 pub mod cia1;
 
-const BASIC: &[u8] = include_bytes!("basic.901226-01.bin");
-const KERNAL: &[u8] = include_bytes!("kernal.901227-03.bin");
-const CHARACTERS: &[u8] = include_bytes!("characters.901225-01.bin");
+/// Nothing drives the data bus, what is read depends on the last VIC fetch.
+const OPEN_BUS: u8 = 0xFF;
 
 pub struct Memory {
-    data: [u8; 0xFFFF],
+    data: [u8; 0x10000],
+    io_port: IoPort,
+    pla: Pla,
+    roms: Roms,
+    /// In the order they were attached, which is also the order they answer reads in.
+    expansion_port: Vec<Box<dyn ExpansionPort>>,
+    /// The VIC holds the bus, devices have to wait with their DMA.
+    vic_ba_low: bool,
     color_ram: ColorRAM,
     cia1: Cia1,
     cia2: Cia2,
@@ -21,12 +36,14 @@ pub struct Memory {
 }
 
 impl Memory {
-    pub fn new() -> Memory {
-        let mut data = [0; 0xFFFF];
-        data[0x0000] = 0x2F;
-        data[0x0001] = 0x37;
+    pub fn new(roms: Roms) -> Memory {
         Memory {
-            data,
+            data: [0; 0x10000],
+            io_port: IoPort::new(),
+            pla: Pla::new(),
+            roms,
+            expansion_port: Vec::new(),
+            vic_ba_low: false,
             color_ram: ColorRAM::new(),
             cia1: Cia1::new(),
             cia2: Cia2::new(),
@@ -35,103 +52,153 @@ impl Memory {
     }
 
     fn get(&mut self, loc: usize) -> u8 {
-        if loc >= 0xA000 && loc <= 0xBFFF && (self.data[0x0001] & 0x03) == 0x03 {
-            return BASIC[loc - 0xA000];
+        if loc <= 0x0001 {
+            return self.io_port.get(loc);
         }
-        if loc >= 0xE000 && loc <= 0xFFFF && (self.data[0x0001] & 0x02) == 0x02 {
-            return KERNAL[loc - 0xE000];
+        match self.pla.bank(loc) {
+            Bank::Ram => self.data[loc],
+            Bank::Basic => self.roms.basic.get(loc - 0xA000),
+            Bank::Kernal => self.roms.kernal.get(loc - 0xE000),
+            Bank::Characters => self.roms.characters.get(loc - 0xD000),
+            Bank::Io => self.get_io(loc),
+            Bank::RomL => self.read_expansion_port(|d| d.read_roml(loc & 0x1FFF)),
+            Bank::RomH => self.read_expansion_port(|d| d.read_romh(loc & 0x1FFF)),
+            Bank::Open => OPEN_BUS,
         }
-        if loc >= 0xD000 && loc <= 0xDFFF && (self.data[0x0001] & 0x03) != 0x00 {
-            if (self.data[0x0001] & 0x04) == 0x00 {
-                return CHARACTERS[loc - 0xD000];
-            }
-            if loc >= 0xD000 && loc <= 0xD3FF {
-                return self.gpu.get(((loc - 0xD000) % 64) + 0xD000);
-            }
-            if loc >= 0xD400 && loc <= 0xD7FF {
-                //debug!("SID get {:04X}", loc);
-            } else if loc >= 0xD800 && loc <= 0xDBFF {
-                return self.color_ram.get(loc - 0xD800);
-            } else if loc >= 0xDC00 && loc <= 0xDCFF {
-                return self.cia1.get(((loc - 0xDC00) % 16) + 0xDC00);
-            } else if loc >= 0xDD00 && loc <= 0xDDFF {
-                return self.cia2.get(((loc - 0xDD00) % 16) + 0xDD00);
-            } else if loc >= 0xDE00 && loc <= 0xDEFF {
-                //debug!("I/O Area #1 get {:04X}", loc);
-            } else if loc >= 0xDF00 && loc <= 0xDFFF {
-                //debug!("I/O Area #2 get {:04X}", loc);
-            }
+    }
+
+    fn get_io(&mut self, loc: usize) -> u8 {
+        if (0xD000..=0xD3FF).contains(&loc) {
+            return self.gpu.get(((loc - 0xD000) % 64) + 0xD000);
+        }
+        if (0xD400..=0xD7FF).contains(&loc) {
+            //debug!("SID get {:04X}", loc);
+        } else if (0xD800..=0xDBFF).contains(&loc) {
+            return self.color_ram.get(loc - 0xD800);
+        } else if (0xDC00..=0xDCFF).contains(&loc) {
+            return self.cia1.get(((loc - 0xDC00) % 16) + 0xDC00);
+        } else if (0xDD00..=0xDDFF).contains(&loc) {
+            return self.cia2.get(((loc - 0xDD00) % 16) + 0xDD00);
+        } else if (0xDE00..=0xDEFF).contains(&loc) {
+            return self.read_expansion_port(|d| d.read_io1(loc));
+        } else if (0xDF00..=0xDFFF).contains(&loc) {
+            return self.read_expansion_port(|d| d.read_io2(loc));
         }
         self.data[loc]
     }
 
     pub fn get_from_gpu(&self, loc: usize) -> u8 {
-        if (loc >= 0x1000 && loc < 0x2000) || (loc >= 0x9000 && loc < 0xA000) {
-            return CHARACTERS[(loc % 0x8000) - 0x1000];
+        if self.pla.ultimax() {
+            // the VIC sees the upper 4K of ROMH
+            if loc & 0x3000 == 0x3000 {
+                return self.expansion_port.iter()
+                    .find_map(|d| d.peek_romh(0x1000 | (loc & 0x0FFF)))
+                    .unwrap_or(OPEN_BUS);
+            }
+        } else if (0x1000..0x2000).contains(&loc) || (0x9000..0xA000).contains(&loc) {
+            return self.roms.characters.get((loc % 0x8000) - 0x1000);
         }
         self.data[loc]
     }
 
     fn set(&mut self, loc: usize, value: u8) {
-        if loc >= 0xD000 && loc <= 0xDFFF && (self.data[0x0001] & 0x03) != 0x00 {
-            if (self.data[0x0001] & 0x04) == 0x00 {
-                //charrom noop
-            }
-            if (self.data[0x0001] & 0x04) != 0x00 {
-                if loc >= 0xD000 && loc <= 0xD3FF {
-                    self.gpu.set(((loc - 0xD000) % 64) + 0xD000, value);
-                } else if loc >= 0xD400 && loc <= 0xD7FF {
-                    //debug!("SID get {:04X} = {:02X}", loc, value);
-                } else if loc >= 0xD800 && loc <= 0xDBFF {
-                    self.color_ram.set(loc - 0xD800, value);
-                } else if loc >= 0xDC00 && loc <= 0xDCFF {
-                    return self.cia1.set(((loc - 0xDC00) % 16) + 0xDC00, value);
-                } else if loc >= 0xDD00 && loc <= 0xDDFF {
-                    return self.cia2.set(((loc - 0xDD00) % 16) + 0xDD00, value);
-                } else if loc >= 0xDE00 && loc <= 0xDEFF {
-                    //debug!("I/O Area #1 set {:04X} = {:02X}", loc, value);
-                } else if loc >= 0xDF00 && loc <= 0xDFFF {
-                    //debug!("I/O Area #2 set {:04X} = {:02X}", loc, value);
+        if loc <= 0x0001 {
+            self.io_port.set(loc, value);
+            self.pla.update(self.io_port.memory_configuration());
+            return;
+        }
+        for device in self.expansion_port.iter_mut() {
+            device.snoop_write(loc as u16, value);
+        }
+        match self.pla.bank(loc) {
+            Bank::Io => self.set_io(loc, value),
+            bank @ (Bank::RomL | Bank::RomH) => {
+                if bank == Bank::RomL {
+                    self.write_expansion_port(|d| d.write_roml(loc & 0x1FFF, value));
+                } else {
+                    self.write_expansion_port(|d| d.write_romh(loc & 0x1FFF, value));
+                }
+                // there is no RAM behind the cartridge ROM in Ultimax mode
+                if !self.pla.ultimax() {
+                    self.data[loc] = value;
                 }
-                return;
             }
+            Bank::Open => {}
+            _ => self.data[loc] = value,
         }
-        // if loc == 0x0001 {
-        //     let a = loc;
-        //println!("set mem {:04X}={}", loc, value);
-        // }
-        if loc == (0xc400 + 46) {
-            //debug!("memset {:04X} = {:02X}", loc, value);
+    }
+
+    fn set_io(&mut self, loc: usize, value: u8) {
+        if (0xD000..=0xD3FF).contains(&loc) {
+            self.gpu.set(((loc - 0xD000) % 64) + 0xD000, value);
+        } else if (0xD400..=0xD7FF).contains(&loc) {
+            //debug!("SID get {:04X} = {:02X}", loc, value);
+        } else if (0xD800..=0xDBFF).contains(&loc) {
+            self.color_ram.set(loc - 0xD800, value);
+        } else if (0xDC00..=0xDCFF).contains(&loc) {
+            self.cia1.set(((loc - 0xDC00) % 16) + 0xDC00, value);
+        } else if (0xDD00..=0xDDFF).contains(&loc) {
+            self.cia2.set(((loc - 0xDD00) % 16) + 0xDD00, value);
+        } else if (0xDE00..=0xDEFF).contains(&loc) {
+            self.write_expansion_port(|d| d.write_io1(loc, value));
+        } else if (0xDF00..=0xDFFF).contains(&loc) {
+            self.write_expansion_port(|d| d.write_io2(loc, value));
         }
-        self.data[loc] = value;
     }
 
-    pub fn set_from_low(&mut self, low: u8, value: u8) {
-        self.set(low as usize, value);
+    /// Every device sees the read, the first one that answers drives the data bus.
+    fn read_expansion_port(&mut self, mut read: impl FnMut(&mut dyn ExpansionPort) -> Option<u8>) -> u8 {
+        let mut value = None;
+        for device in self.expansion_port.iter_mut() {
+            value = value.or(read(device.as_mut()));
+        }
+        self.update_expansion_port_lines();
+        value.unwrap_or(OPEN_BUS)
     }
 
-    pub fn get_from_low(&mut self, low: u8) -> u8 {
-        self.get(low as usize)
+    fn write_expansion_port(&mut self, mut write: impl FnMut(&mut dyn ExpansionPort)) {
+        for device in self.expansion_port.iter_mut() {
+            write(device.as_mut());
+        }
+        self.update_expansion_port_lines();
+    }
+
+    pub fn attach(&mut self, device: Box<dyn ExpansionPort>) {
+        self.expansion_port.push(device);
+        self.update_expansion_port_lines();
     }
 
-    pub fn set_from_low_high(&mut self, low: u8, high: u8, value: u8) {
-        self.set((((low as u16) & 0x00FF) | (((high as u16) << 8) & 0xFF00)) as usize, value);
+    /// The first attached device of type `T`.
+    pub fn device<T: ExpansionPort>(&self) -> Option<&T> {
+        self.expansion_port.iter().find_map(|d| (d.as_ref() as &dyn Any).downcast_ref::<T>())
     }
 
-    pub fn get_from_word(&mut self, loc: u16) -> u8 {
-        self.get(loc as usize)
+    pub fn device_mut<T: ExpansionPort>(&mut self) -> Option<&mut T> {
+        self.expansion_port.iter_mut().find_map(|d| (d.as_mut() as &mut dyn Any).downcast_mut::<T>())
     }
 
-    pub fn get_from_low_high(&mut self, low: u8, high: u8) -> u8 {
-        self.get((((low as u16) & 0x00FF) | (((high as u16) << 8) & 0xFF00)) as usize)
+    /// Cartridges switch EXROM and GAME on I/O accesses and over time.
+    fn update_expansion_port_lines(&mut self) {
+        let exrom = self.expansion_port.iter().all(|d| d.exrom());
+        let game = self.expansion_port.iter().all(|d| d.game());
+        self.pla.set_cartridge_lines(exrom, game);
+        self.pla.update(self.io_port.memory_configuration());
     }
 
-    pub fn set_stack(&mut self, sp: u8, value: u8) {
-        self.data[sp as usize | 0x0100] = value;
+    pub fn set_vic_ba_low(&mut self, ba_low: bool) {
+        self.vic_ba_low = ba_low;
     }
 
-    pub fn get_stack(&self, sp: u8) -> u8 {
-        self.data[sp as usize | 0x0100]
+    pub fn expansion_port_irq(&self) -> bool {
+        self.expansion_port.iter().any(|d| d.irq())
+    }
+
+    pub fn expansion_port_nmi(&self) -> bool {
+        self.expansion_port.iter().any(|d| d.nmi())
+    }
+
+    pub fn expansion_port_reset(&self) -> bool {
+        self.expansion_port.iter().any(|d| d.reset())
     }
 
     pub fn cia1(&mut self) -> &mut Cia1 {
@@ -142,11 +209,48 @@ impl Memory {
         &self.cia2
     }
 
+    pub fn cia2_mut(&mut self) -> &mut Cia2 {
+        &mut self.cia2
+    }
+
     pub fn gpu(&self) -> &Gpu {
         &self.gpu
     }
 
+    pub fn gpu_mut(&mut self) -> &mut Gpu {
+        &mut self.gpu
+    }
+
     pub fn color_ram(&self) -> &ColorRAM {
         &self.color_ram
     }
+}
+
+impl Bus for Memory {
+    fn read(&mut self, address: u16) -> u8 {
+        self.get(address as usize)
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        self.set(address as usize, value);
+    }
+
+    fn cycle(&mut self) {
+        self.io_port.tick();
+        if self.expansion_port.is_empty() {
+            return;
+        }
+        // DMA goes through the same memory map the CPU sees, minus the devices themselves
+        let mut devices = std::mem::take(&mut self.expansion_port);
+        let ba_low = self.vic_ba_low;
+        for device in devices.iter_mut() {
+            device.tick(self, ba_low);
+        }
+        self.expansion_port = devices;
+        self.update_expansion_port_lines();
+    }
+
+    fn dma(&self) -> bool {
+        self.expansion_port.iter().any(|d| d.dma())
+    }
 }
\ No newline at end of file
@@ -0,0 +1,129 @@
+/// What the CPU sees in an area of the address space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bank {
+    Ram,
+    Basic,
+    Kernal,
+    Characters,
+    Io,
+    /// Cartridge ROM at $8000 (and the Ultimax ROML).
+    RomL,
+    /// Cartridge ROM at $A000, or at $E000 in Ultimax mode.
+    RomH,
+    /// Not connected in Ultimax mode, reads float and writes go nowhere.
+    Open,
+}
+
+/// Area switched by the PLA for each 4K page: $0000, $1000-$7FFF, $8000, $A000, $C000, $D000 and
+/// $E000.
+const AREAS: [usize; 16] = [0, 1, 1, 1, 1, 1, 1, 1, 2, 2, 3, 3, 4, 5, 6, 6];
+
+/// Indexed by EXROM, GAME, CHAREN, HIRAM and LORAM (bit 4 to 0), columns are the areas.
+static CONFIGURATIONS: [[Bank; 7]; 32] = [
+    /*  0 00000 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /*  1 00001 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /*  2 00010 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::RomH, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /*  3 00011 */ [Bank::Ram, Bank::Ram, Bank::RomL, Bank::RomH, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /*  4 00100 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /*  5 00101 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Io, Bank::Ram],
+    /*  6 00110 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::RomH, Bank::Ram, Bank::Io, Bank::Kernal],
+    /*  7 00111 */ [Bank::Ram, Bank::Ram, Bank::RomL, Bank::RomH, Bank::Ram, Bank::Io, Bank::Kernal],
+    /*  8 01000 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /*  9 01001 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Characters, Bank::Ram],
+    /* 10 01010 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /* 11 01011 */ [Bank::Ram, Bank::Ram, Bank::RomL, Bank::Basic, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /* 12 01100 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /* 13 01101 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Io, Bank::Ram],
+    /* 14 01110 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Io, Bank::Kernal],
+    /* 15 01111 */ [Bank::Ram, Bank::Ram, Bank::RomL, Bank::Basic, Bank::Ram, Bank::Io, Bank::Kernal],
+    /* 16 10000 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 17 10001 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 18 10010 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 19 10011 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 20 10100 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 21 10101 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 22 10110 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 23 10111 */ [Bank::Ram, Bank::Open, Bank::RomL, Bank::Open, Bank::Open, Bank::Io, Bank::RomH],
+    /* 24 11000 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /* 25 11001 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Characters, Bank::Ram],
+    /* 26 11010 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /* 27 11011 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Basic, Bank::Ram, Bank::Characters, Bank::Kernal],
+    /* 28 11100 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram],
+    /* 29 11101 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Io, Bank::Ram],
+    /* 30 11110 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Ram, Bank::Io, Bank::Kernal],
+    /* 31 11111 */ [Bank::Ram, Bank::Ram, Bank::Ram, Bank::Basic, Bank::Ram, Bank::Io, Bank::Kernal],
+];
+
+/// The PLA decodes the 6510 port bits LORAM, HIRAM and CHAREN together with the cartridge lines
+/// EXROM and GAME (both active low) into the memory map.
+pub struct Pla {
+    configuration: &'static [Bank; 7],
+    exrom: bool,
+    game: bool,
+}
+
+impl Pla {
+    pub fn new() -> Pla {
+        Pla {
+            configuration: &CONFIGURATIONS[31],
+            exrom: true,
+            game: true,
+        }
+    }
+
+    /// `port` holds LORAM, HIRAM and CHAREN as output by the 6510 port.
+    pub fn update(&mut self, port: u8) {
+        let index = ((self.exrom as usize) << 4) | ((self.game as usize) << 3) | (port as usize & 0x07);
+        self.configuration = &CONFIGURATIONS[index];
+    }
+
+    /// Sets the cartridge lines, true is high (no cartridge), `update` has to follow.
+    pub fn set_cartridge_lines(&mut self, exrom: bool, game: bool) {
+        self.exrom = exrom;
+        self.game = game;
+    }
+
+    pub fn bank(&self, loc: usize) -> Bank {
+        self.configuration[AREAS[loc >> 12]]
+    }
+
+    /// In Ultimax mode the VIC sees ROMH instead of RAM at $3000-$3FFF of every bank, and no
+    /// character ROM.
+    pub fn ultimax(&self) -> bool {
+        self.exrom && !self.game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configurations() {
+        let mut pla = Pla::new();
+        pla.update(0x07);
+        assert_eq!(pla.bank(0xA000), Bank::Basic);
+        assert_eq!(pla.bank(0xD020), Bank::Io);
+        pla.update(0x03);
+        assert_eq!(pla.bank(0xD000), Bank::Characters);
+        pla.update(0x01);
+        assert_eq!(pla.bank(0xE000), Bank::Ram);
+        assert_eq!(pla.bank(0xD000), Bank::Characters);
+
+        // 16K cartridge, HIRAM low hides the character ROM
+        pla.set_cartridge_lines(false, false);
+        pla.update(0x01);
+        assert_eq!(pla.bank(0xD000), Bank::Ram);
+        pla.update(0x07);
+        assert_eq!(pla.bank(0x8000), Bank::RomL);
+        assert_eq!(pla.bank(0xBFFF), Bank::RomH);
+
+        pla.set_cartridge_lines(true, false);
+        pla.update(0x00);
+        assert!(pla.ultimax());
+        assert_eq!(pla.bank(0x0FFF), Bank::Ram);
+        assert_eq!(pla.bank(0x1000), Bank::Open);
+        assert_eq!(pla.bank(0xD000), Bank::Io);
+        assert_eq!(pla.bank(0xFFFF), Bank::RomH);
+    }
+}
@@ -1,8 +1,20 @@
 pub mod emulator;
 mod memory;
 mod cpu;
+#[cfg(test)]
+mod cpu_tests;
 mod logger;
 mod addressing;
-mod gpu;
+mod opcodes;
+pub mod bus;
+pub mod gpu;
+#[cfg(test)]
+mod single_step_tests;
 mod timer_a;
-pub mod keyboard;
\ No newline at end of file
+mod irq_line;
+pub mod keyboard;
+pub mod roms;
+pub mod cartridge;
+pub mod reu;
+pub mod georam;
+pub mod expansion_port;
@@ -0,0 +1,470 @@
+use crate::emulator::cpu::Cpu;
+use Operation::*;
+
+/// How an instruction finds its operand, as far as the assembler syntax is concerned. The cycle
+/// by cycle behaviour lives in `Addressing`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndexedIndirect,
+    IndirectIndexed,
+    Relative,
+}
+
+impl Mode {
+    pub const fn bytes(self) -> u8 {
+        match self {
+            Mode::Implied | Mode::Accumulator => 1,
+            Mode::Absolute | Mode::AbsoluteX | Mode::AbsoluteY | Mode::Indirect => 3,
+            _ => 2,
+        }
+    }
+}
+
+/// What an instruction does with its operand, and the function that does it. Together with the
+/// mode this picks the bus sequence in `Addressing`.
+#[derive(Clone, Copy)]
+pub enum Operation {
+    Read(fn(&mut Cpu, u8)),
+    /// Writes the value the function returns.
+    Write(fn(&mut Cpu) -> u8),
+    /// SHA, SHX, SHY and TAS: the function gets the high byte of the base address plus 1.
+    WriteUnstable(fn(&mut Cpu, u8) -> u8),
+    /// Reads, writes the unmodified value back and then the result, or works on A.
+    Modify(fn(&mut Cpu, u8) -> u8),
+    Implied(fn(&mut Cpu)),
+    /// Returns whether the branch is taken.
+    Branch(fn(&mut Cpu) -> bool),
+    Push(fn(&mut Cpu) -> u8),
+    Pull(fn(&mut Cpu, u8)),
+    Brk,
+    Jsr,
+    Rts,
+    Rti,
+    Jmp,
+    Jam,
+}
+
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    pub mode: Mode,
+    pub operation: Operation,
+    pub bytes: u8,
+    /// Cycles without a page crossing, and for branches when not taken.
+    pub cycles: u8,
+    /// One more cycle when the indexed address crosses a page, otherwise the fix-up cycle is
+    /// skipped. Branches take one more when taken and another one when the target is on a
+    /// different page.
+    pub page_cross: bool,
+    pub legal: bool,
+}
+
+impl Opcode {
+    const fn legal(mnemonic: &'static str, mode: Mode, operation: Operation, cycles: u8, page_cross: bool) -> Opcode {
+        Opcode { mnemonic, mode, operation, bytes: mode.bytes(), cycles, page_cross, legal: true }
+    }
+
+    const fn illegal(mnemonic: &'static str, mode: Mode, operation: Operation, cycles: u8, page_cross: bool) -> Opcode {
+        Opcode { mnemonic, mode, operation, bytes: mode.bytes(), cycles, page_cross, legal: false }
+    }
+
+    /// Whether cycle `sub_tick` of the instruction, 1 being the opcode fetch, writes to the bus.
+    pub fn writes_in(&self, sub_tick: u8) -> bool {
+        self.writes() & (1 << sub_tick) != 0
+    }
+
+    /// Bit n is set when cycle n writes: the last one for stores and pushes, the last two for
+    /// read-modify-write on memory, and the pushes of JSR and BRK.
+    pub fn writes(&self) -> u16 {
+        match self.operation {
+            Write(_) | WriteUnstable(_) | Push(_) => 1 << self.cycles,
+            Modify(_) if self.mode != Mode::Accumulator => 3 << (self.cycles - 1),
+            Jsr => 3 << 4,
+            Brk => 7 << 3,
+            _ => 0,
+        }
+    }
+}
+
+/// NMOS 6502/6510 opcodes, illegal ones with the mnemonics of "No More Secrets".
+pub static OPCODES: [Opcode; 256] = [
+    /* 00 */ Opcode::legal("BRK", Mode::Implied, Brk, 7, false),
+    /* 01 */ Opcode::legal("ORA", Mode::IndexedIndirect, Read(Cpu::ora), 6, false),
+    /* 02 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 03 */ Opcode::illegal("SLO", Mode::IndexedIndirect, Modify(Cpu::slo), 8, false),
+    /* 04 */ Opcode::illegal("NOP", Mode::ZeroPage, Read(Cpu::nop_read), 3, false),
+    /* 05 */ Opcode::legal("ORA", Mode::ZeroPage, Read(Cpu::ora), 3, false),
+    /* 06 */ Opcode::legal("ASL", Mode::ZeroPage, Modify(Cpu::asl), 5, false),
+    /* 07 */ Opcode::illegal("SLO", Mode::ZeroPage, Modify(Cpu::slo), 5, false),
+    /* 08 */ Opcode::legal("PHP", Mode::Implied, Push(Cpu::php), 3, false),
+    /* 09 */ Opcode::legal("ORA", Mode::Immediate, Read(Cpu::ora), 2, false),
+    /* 0A */ Opcode::legal("ASL", Mode::Accumulator, Modify(Cpu::asl), 2, false),
+    /* 0B */ Opcode::illegal("ANC", Mode::Immediate, Read(Cpu::anc), 2, false),
+    /* 0C */ Opcode::illegal("NOP", Mode::Absolute, Read(Cpu::nop_read), 4, false),
+    /* 0D */ Opcode::legal("ORA", Mode::Absolute, Read(Cpu::ora), 4, false),
+    /* 0E */ Opcode::legal("ASL", Mode::Absolute, Modify(Cpu::asl), 6, false),
+    /* 0F */ Opcode::illegal("SLO", Mode::Absolute, Modify(Cpu::slo), 6, false),
+    /* 10 */ Opcode::legal("BPL", Mode::Relative, Branch(Cpu::bpl), 2, true),
+    /* 11 */ Opcode::legal("ORA", Mode::IndirectIndexed, Read(Cpu::ora), 5, true),
+    /* 12 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 13 */ Opcode::illegal("SLO", Mode::IndirectIndexed, Modify(Cpu::slo), 8, false),
+    /* 14 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* 15 */ Opcode::legal("ORA", Mode::ZeroPageX, Read(Cpu::ora), 4, false),
+    /* 16 */ Opcode::legal("ASL", Mode::ZeroPageX, Modify(Cpu::asl), 6, false),
+    /* 17 */ Opcode::illegal("SLO", Mode::ZeroPageX, Modify(Cpu::slo), 6, false),
+    /* 18 */ Opcode::legal("CLC", Mode::Implied, Implied(Cpu::clc), 2, false),
+    /* 19 */ Opcode::legal("ORA", Mode::AbsoluteY, Read(Cpu::ora), 4, true),
+    /* 1A */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* 1B */ Opcode::illegal("SLO", Mode::AbsoluteY, Modify(Cpu::slo), 7, false),
+    /* 1C */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* 1D */ Opcode::legal("ORA", Mode::AbsoluteX, Read(Cpu::ora), 4, true),
+    /* 1E */ Opcode::legal("ASL", Mode::AbsoluteX, Modify(Cpu::asl), 7, false),
+    /* 1F */ Opcode::illegal("SLO", Mode::AbsoluteX, Modify(Cpu::slo), 7, false),
+    /* 20 */ Opcode::legal("JSR", Mode::Absolute, Jsr, 6, false),
+    /* 21 */ Opcode::legal("AND", Mode::IndexedIndirect, Read(Cpu::and), 6, false),
+    /* 22 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 23 */ Opcode::illegal("RLA", Mode::IndexedIndirect, Modify(Cpu::rla), 8, false),
+    /* 24 */ Opcode::legal("BIT", Mode::ZeroPage, Read(Cpu::bit), 3, false),
+    /* 25 */ Opcode::legal("AND", Mode::ZeroPage, Read(Cpu::and), 3, false),
+    /* 26 */ Opcode::legal("ROL", Mode::ZeroPage, Modify(Cpu::rol), 5, false),
+    /* 27 */ Opcode::illegal("RLA", Mode::ZeroPage, Modify(Cpu::rla), 5, false),
+    /* 28 */ Opcode::legal("PLP", Mode::Implied, Pull(Cpu::plp), 4, false),
+    /* 29 */ Opcode::legal("AND", Mode::Immediate, Read(Cpu::and), 2, false),
+    /* 2A */ Opcode::legal("ROL", Mode::Accumulator, Modify(Cpu::rol), 2, false),
+    /* 2B */ Opcode::illegal("ANC", Mode::Immediate, Read(Cpu::anc), 2, false),
+    /* 2C */ Opcode::legal("BIT", Mode::Absolute, Read(Cpu::bit), 4, false),
+    /* 2D */ Opcode::legal("AND", Mode::Absolute, Read(Cpu::and), 4, false),
+    /* 2E */ Opcode::legal("ROL", Mode::Absolute, Modify(Cpu::rol), 6, false),
+    /* 2F */ Opcode::illegal("RLA", Mode::Absolute, Modify(Cpu::rla), 6, false),
+    /* 30 */ Opcode::legal("BMI", Mode::Relative, Branch(Cpu::bmi), 2, true),
+    /* 31 */ Opcode::legal("AND", Mode::IndirectIndexed, Read(Cpu::and), 5, true),
+    /* 32 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 33 */ Opcode::illegal("RLA", Mode::IndirectIndexed, Modify(Cpu::rla), 8, false),
+    /* 34 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* 35 */ Opcode::legal("AND", Mode::ZeroPageX, Read(Cpu::and), 4, false),
+    /* 36 */ Opcode::legal("ROL", Mode::ZeroPageX, Modify(Cpu::rol), 6, false),
+    /* 37 */ Opcode::illegal("RLA", Mode::ZeroPageX, Modify(Cpu::rla), 6, false),
+    /* 38 */ Opcode::legal("SEC", Mode::Implied, Implied(Cpu::sec), 2, false),
+    /* 39 */ Opcode::legal("AND", Mode::AbsoluteY, Read(Cpu::and), 4, true),
+    /* 3A */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* 3B */ Opcode::illegal("RLA", Mode::AbsoluteY, Modify(Cpu::rla), 7, false),
+    /* 3C */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* 3D */ Opcode::legal("AND", Mode::AbsoluteX, Read(Cpu::and), 4, true),
+    /* 3E */ Opcode::legal("ROL", Mode::AbsoluteX, Modify(Cpu::rol), 7, false),
+    /* 3F */ Opcode::illegal("RLA", Mode::AbsoluteX, Modify(Cpu::rla), 7, false),
+    /* 40 */ Opcode::legal("RTI", Mode::Implied, Rti, 6, false),
+    /* 41 */ Opcode::legal("EOR", Mode::IndexedIndirect, Read(Cpu::eor), 6, false),
+    /* 42 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 43 */ Opcode::illegal("SRE", Mode::IndexedIndirect, Modify(Cpu::sre), 8, false),
+    /* 44 */ Opcode::illegal("NOP", Mode::ZeroPage, Read(Cpu::nop_read), 3, false),
+    /* 45 */ Opcode::legal("EOR", Mode::ZeroPage, Read(Cpu::eor), 3, false),
+    /* 46 */ Opcode::legal("LSR", Mode::ZeroPage, Modify(Cpu::lsr), 5, false),
+    /* 47 */ Opcode::illegal("SRE", Mode::ZeroPage, Modify(Cpu::sre), 5, false),
+    /* 48 */ Opcode::legal("PHA", Mode::Implied, Push(Cpu::pha), 3, false),
+    /* 49 */ Opcode::legal("EOR", Mode::Immediate, Read(Cpu::eor), 2, false),
+    /* 4A */ Opcode::legal("LSR", Mode::Accumulator, Modify(Cpu::lsr), 2, false),
+    /* 4B */ Opcode::illegal("ALR", Mode::Immediate, Read(Cpu::alr), 2, false),
+    /* 4C */ Opcode::legal("JMP", Mode::Absolute, Jmp, 3, false),
+    /* 4D */ Opcode::legal("EOR", Mode::Absolute, Read(Cpu::eor), 4, false),
+    /* 4E */ Opcode::legal("LSR", Mode::Absolute, Modify(Cpu::lsr), 6, false),
+    /* 4F */ Opcode::illegal("SRE", Mode::Absolute, Modify(Cpu::sre), 6, false),
+    /* 50 */ Opcode::legal("BVC", Mode::Relative, Branch(Cpu::bvc), 2, true),
+    /* 51 */ Opcode::legal("EOR", Mode::IndirectIndexed, Read(Cpu::eor), 5, true),
+    /* 52 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 53 */ Opcode::illegal("SRE", Mode::IndirectIndexed, Modify(Cpu::sre), 8, false),
+    /* 54 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* 55 */ Opcode::legal("EOR", Mode::ZeroPageX, Read(Cpu::eor), 4, false),
+    /* 56 */ Opcode::legal("LSR", Mode::ZeroPageX, Modify(Cpu::lsr), 6, false),
+    /* 57 */ Opcode::illegal("SRE", Mode::ZeroPageX, Modify(Cpu::sre), 6, false),
+    /* 58 */ Opcode::legal("CLI", Mode::Implied, Implied(Cpu::cli), 2, false),
+    /* 59 */ Opcode::legal("EOR", Mode::AbsoluteY, Read(Cpu::eor), 4, true),
+    /* 5A */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* 5B */ Opcode::illegal("SRE", Mode::AbsoluteY, Modify(Cpu::sre), 7, false),
+    /* 5C */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* 5D */ Opcode::legal("EOR", Mode::AbsoluteX, Read(Cpu::eor), 4, true),
+    /* 5E */ Opcode::legal("LSR", Mode::AbsoluteX, Modify(Cpu::lsr), 7, false),
+    /* 5F */ Opcode::illegal("SRE", Mode::AbsoluteX, Modify(Cpu::sre), 7, false),
+    /* 60 */ Opcode::legal("RTS", Mode::Implied, Rts, 6, false),
+    /* 61 */ Opcode::legal("ADC", Mode::IndexedIndirect, Read(Cpu::adc), 6, false),
+    /* 62 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 63 */ Opcode::illegal("RRA", Mode::IndexedIndirect, Modify(Cpu::rra), 8, false),
+    /* 64 */ Opcode::illegal("NOP", Mode::ZeroPage, Read(Cpu::nop_read), 3, false),
+    /* 65 */ Opcode::legal("ADC", Mode::ZeroPage, Read(Cpu::adc), 3, false),
+    /* 66 */ Opcode::legal("ROR", Mode::ZeroPage, Modify(Cpu::ror), 5, false),
+    /* 67 */ Opcode::illegal("RRA", Mode::ZeroPage, Modify(Cpu::rra), 5, false),
+    /* 68 */ Opcode::legal("PLA", Mode::Implied, Pull(Cpu::pla), 4, false),
+    /* 69 */ Opcode::legal("ADC", Mode::Immediate, Read(Cpu::adc), 2, false),
+    /* 6A */ Opcode::legal("ROR", Mode::Accumulator, Modify(Cpu::ror), 2, false),
+    /* 6B */ Opcode::illegal("ARR", Mode::Immediate, Read(Cpu::arr), 2, false),
+    /* 6C */ Opcode::legal("JMP", Mode::Indirect, Jmp, 5, false),
+    /* 6D */ Opcode::legal("ADC", Mode::Absolute, Read(Cpu::adc), 4, false),
+    /* 6E */ Opcode::legal("ROR", Mode::Absolute, Modify(Cpu::ror), 6, false),
+    /* 6F */ Opcode::illegal("RRA", Mode::Absolute, Modify(Cpu::rra), 6, false),
+    /* 70 */ Opcode::legal("BVS", Mode::Relative, Branch(Cpu::bvs), 2, true),
+    /* 71 */ Opcode::legal("ADC", Mode::IndirectIndexed, Read(Cpu::adc), 5, true),
+    /* 72 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 73 */ Opcode::illegal("RRA", Mode::IndirectIndexed, Modify(Cpu::rra), 8, false),
+    /* 74 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* 75 */ Opcode::legal("ADC", Mode::ZeroPageX, Read(Cpu::adc), 4, false),
+    /* 76 */ Opcode::legal("ROR", Mode::ZeroPageX, Modify(Cpu::ror), 6, false),
+    /* 77 */ Opcode::illegal("RRA", Mode::ZeroPageX, Modify(Cpu::rra), 6, false),
+    /* 78 */ Opcode::legal("SEI", Mode::Implied, Implied(Cpu::sei), 2, false),
+    /* 79 */ Opcode::legal("ADC", Mode::AbsoluteY, Read(Cpu::adc), 4, true),
+    /* 7A */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* 7B */ Opcode::illegal("RRA", Mode::AbsoluteY, Modify(Cpu::rra), 7, false),
+    /* 7C */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* 7D */ Opcode::legal("ADC", Mode::AbsoluteX, Read(Cpu::adc), 4, true),
+    /* 7E */ Opcode::legal("ROR", Mode::AbsoluteX, Modify(Cpu::ror), 7, false),
+    /* 7F */ Opcode::illegal("RRA", Mode::AbsoluteX, Modify(Cpu::rra), 7, false),
+    /* 80 */ Opcode::illegal("NOP", Mode::Immediate, Read(Cpu::nop_read), 2, false),
+    /* 81 */ Opcode::legal("STA", Mode::IndexedIndirect, Write(Cpu::sta), 6, false),
+    /* 82 */ Opcode::illegal("NOP", Mode::Immediate, Read(Cpu::nop_read), 2, false),
+    /* 83 */ Opcode::illegal("SAX", Mode::IndexedIndirect, Write(Cpu::sax), 6, false),
+    /* 84 */ Opcode::legal("STY", Mode::ZeroPage, Write(Cpu::sty), 3, false),
+    /* 85 */ Opcode::legal("STA", Mode::ZeroPage, Write(Cpu::sta), 3, false),
+    /* 86 */ Opcode::legal("STX", Mode::ZeroPage, Write(Cpu::stx), 3, false),
+    /* 87 */ Opcode::illegal("SAX", Mode::ZeroPage, Write(Cpu::sax), 3, false),
+    /* 88 */ Opcode::legal("DEY", Mode::Implied, Implied(Cpu::dey), 2, false),
+    /* 89 */ Opcode::illegal("NOP", Mode::Immediate, Read(Cpu::nop_read), 2, false),
+    /* 8A */ Opcode::legal("TXA", Mode::Implied, Implied(Cpu::txa), 2, false),
+    /* 8B */ Opcode::illegal("ANE", Mode::Immediate, Read(Cpu::ane), 2, false),
+    /* 8C */ Opcode::legal("STY", Mode::Absolute, Write(Cpu::sty), 4, false),
+    /* 8D */ Opcode::legal("STA", Mode::Absolute, Write(Cpu::sta), 4, false),
+    /* 8E */ Opcode::legal("STX", Mode::Absolute, Write(Cpu::stx), 4, false),
+    /* 8F */ Opcode::illegal("SAX", Mode::Absolute, Write(Cpu::sax), 4, false),
+    /* 90 */ Opcode::legal("BCC", Mode::Relative, Branch(Cpu::bcc), 2, true),
+    /* 91 */ Opcode::legal("STA", Mode::IndirectIndexed, Write(Cpu::sta), 6, false),
+    /* 92 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* 93 */ Opcode::illegal("SHA", Mode::IndirectIndexed, WriteUnstable(Cpu::sha), 6, false),
+    /* 94 */ Opcode::legal("STY", Mode::ZeroPageX, Write(Cpu::sty), 4, false),
+    /* 95 */ Opcode::legal("STA", Mode::ZeroPageX, Write(Cpu::sta), 4, false),
+    /* 96 */ Opcode::legal("STX", Mode::ZeroPageY, Write(Cpu::stx), 4, false),
+    /* 97 */ Opcode::illegal("SAX", Mode::ZeroPageY, Write(Cpu::sax), 4, false),
+    /* 98 */ Opcode::legal("TYA", Mode::Implied, Implied(Cpu::tya), 2, false),
+    /* 99 */ Opcode::legal("STA", Mode::AbsoluteY, Write(Cpu::sta), 5, false),
+    /* 9A */ Opcode::legal("TXS", Mode::Implied, Implied(Cpu::txs), 2, false),
+    /* 9B */ Opcode::illegal("TAS", Mode::AbsoluteY, WriteUnstable(Cpu::tas), 5, false),
+    /* 9C */ Opcode::illegal("SHY", Mode::AbsoluteX, WriteUnstable(Cpu::shy), 5, false),
+    /* 9D */ Opcode::legal("STA", Mode::AbsoluteX, Write(Cpu::sta), 5, false),
+    /* 9E */ Opcode::illegal("SHX", Mode::AbsoluteY, WriteUnstable(Cpu::shx), 5, false),
+    /* 9F */ Opcode::illegal("SHA", Mode::AbsoluteY, WriteUnstable(Cpu::sha), 5, false),
+    /* A0 */ Opcode::legal("LDY", Mode::Immediate, Read(Cpu::ldy), 2, false),
+    /* A1 */ Opcode::legal("LDA", Mode::IndexedIndirect, Read(Cpu::lda), 6, false),
+    /* A2 */ Opcode::legal("LDX", Mode::Immediate, Read(Cpu::ldx), 2, false),
+    /* A3 */ Opcode::illegal("LAX", Mode::IndexedIndirect, Read(Cpu::lax), 6, false),
+    /* A4 */ Opcode::legal("LDY", Mode::ZeroPage, Read(Cpu::ldy), 3, false),
+    /* A5 */ Opcode::legal("LDA", Mode::ZeroPage, Read(Cpu::lda), 3, false),
+    /* A6 */ Opcode::legal("LDX", Mode::ZeroPage, Read(Cpu::ldx), 3, false),
+    /* A7 */ Opcode::illegal("LAX", Mode::ZeroPage, Read(Cpu::lax), 3, false),
+    /* A8 */ Opcode::legal("TAY", Mode::Implied, Implied(Cpu::tay), 2, false),
+    /* A9 */ Opcode::legal("LDA", Mode::Immediate, Read(Cpu::lda), 2, false),
+    /* AA */ Opcode::legal("TAX", Mode::Implied, Implied(Cpu::tax), 2, false),
+    /* AB */ Opcode::illegal("LXA", Mode::Immediate, Read(Cpu::lxa), 2, false),
+    /* AC */ Opcode::legal("LDY", Mode::Absolute, Read(Cpu::ldy), 4, false),
+    /* AD */ Opcode::legal("LDA", Mode::Absolute, Read(Cpu::lda), 4, false),
+    /* AE */ Opcode::legal("LDX", Mode::Absolute, Read(Cpu::ldx), 4, false),
+    /* AF */ Opcode::illegal("LAX", Mode::Absolute, Read(Cpu::lax), 4, false),
+    /* B0 */ Opcode::legal("BCS", Mode::Relative, Branch(Cpu::bcs), 2, true),
+    /* B1 */ Opcode::legal("LDA", Mode::IndirectIndexed, Read(Cpu::lda), 5, true),
+    /* B2 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* B3 */ Opcode::illegal("LAX", Mode::IndirectIndexed, Read(Cpu::lax), 5, true),
+    /* B4 */ Opcode::legal("LDY", Mode::ZeroPageX, Read(Cpu::ldy), 4, false),
+    /* B5 */ Opcode::legal("LDA", Mode::ZeroPageX, Read(Cpu::lda), 4, false),
+    /* B6 */ Opcode::legal("LDX", Mode::ZeroPageY, Read(Cpu::ldx), 4, false),
+    /* B7 */ Opcode::illegal("LAX", Mode::ZeroPageY, Read(Cpu::lax), 4, false),
+    /* B8 */ Opcode::legal("CLV", Mode::Implied, Implied(Cpu::clv), 2, false),
+    /* B9 */ Opcode::legal("LDA", Mode::AbsoluteY, Read(Cpu::lda), 4, true),
+    /* BA */ Opcode::legal("TSX", Mode::Implied, Implied(Cpu::tsx), 2, false),
+    /* BB */ Opcode::illegal("LAS", Mode::AbsoluteY, Read(Cpu::las), 4, true),
+    /* BC */ Opcode::legal("LDY", Mode::AbsoluteX, Read(Cpu::ldy), 4, true),
+    /* BD */ Opcode::legal("LDA", Mode::AbsoluteX, Read(Cpu::lda), 4, true),
+    /* BE */ Opcode::legal("LDX", Mode::AbsoluteY, Read(Cpu::ldx), 4, true),
+    /* BF */ Opcode::illegal("LAX", Mode::AbsoluteY, Read(Cpu::lax), 4, true),
+    /* C0 */ Opcode::legal("CPY", Mode::Immediate, Read(Cpu::cpy), 2, false),
+    /* C1 */ Opcode::legal("CMP", Mode::IndexedIndirect, Read(Cpu::cmp), 6, false),
+    /* C2 */ Opcode::illegal("NOP", Mode::Immediate, Read(Cpu::nop_read), 2, false),
+    /* C3 */ Opcode::illegal("DCP", Mode::IndexedIndirect, Modify(Cpu::dcp), 8, false),
+    /* C4 */ Opcode::legal("CPY", Mode::ZeroPage, Read(Cpu::cpy), 3, false),
+    /* C5 */ Opcode::legal("CMP", Mode::ZeroPage, Read(Cpu::cmp), 3, false),
+    /* C6 */ Opcode::legal("DEC", Mode::ZeroPage, Modify(Cpu::dec), 5, false),
+    /* C7 */ Opcode::illegal("DCP", Mode::ZeroPage, Modify(Cpu::dcp), 5, false),
+    /* C8 */ Opcode::legal("INY", Mode::Implied, Implied(Cpu::iny), 2, false),
+    /* C9 */ Opcode::legal("CMP", Mode::Immediate, Read(Cpu::cmp), 2, false),
+    /* CA */ Opcode::legal("DEX", Mode::Implied, Implied(Cpu::dex), 2, false),
+    /* CB */ Opcode::illegal("SBX", Mode::Immediate, Read(Cpu::sbx), 2, false),
+    /* CC */ Opcode::legal("CPY", Mode::Absolute, Read(Cpu::cpy), 4, false),
+    /* CD */ Opcode::legal("CMP", Mode::Absolute, Read(Cpu::cmp), 4, false),
+    /* CE */ Opcode::legal("DEC", Mode::Absolute, Modify(Cpu::dec), 6, false),
+    /* CF */ Opcode::illegal("DCP", Mode::Absolute, Modify(Cpu::dcp), 6, false),
+    /* D0 */ Opcode::legal("BNE", Mode::Relative, Branch(Cpu::bne), 2, true),
+    /* D1 */ Opcode::legal("CMP", Mode::IndirectIndexed, Read(Cpu::cmp), 5, true),
+    /* D2 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* D3 */ Opcode::illegal("DCP", Mode::IndirectIndexed, Modify(Cpu::dcp), 8, false),
+    /* D4 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* D5 */ Opcode::legal("CMP", Mode::ZeroPageX, Read(Cpu::cmp), 4, false),
+    /* D6 */ Opcode::legal("DEC", Mode::ZeroPageX, Modify(Cpu::dec), 6, false),
+    /* D7 */ Opcode::illegal("DCP", Mode::ZeroPageX, Modify(Cpu::dcp), 6, false),
+    /* D8 */ Opcode::legal("CLD", Mode::Implied, Implied(Cpu::cld), 2, false),
+    /* D9 */ Opcode::legal("CMP", Mode::AbsoluteY, Read(Cpu::cmp), 4, true),
+    /* DA */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* DB */ Opcode::illegal("DCP", Mode::AbsoluteY, Modify(Cpu::dcp), 7, false),
+    /* DC */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* DD */ Opcode::legal("CMP", Mode::AbsoluteX, Read(Cpu::cmp), 4, true),
+    /* DE */ Opcode::legal("DEC", Mode::AbsoluteX, Modify(Cpu::dec), 7, false),
+    /* DF */ Opcode::illegal("DCP", Mode::AbsoluteX, Modify(Cpu::dcp), 7, false),
+    /* E0 */ Opcode::legal("CPX", Mode::Immediate, Read(Cpu::cpx), 2, false),
+    /* E1 */ Opcode::legal("SBC", Mode::IndexedIndirect, Read(Cpu::sbc), 6, false),
+    /* E2 */ Opcode::illegal("NOP", Mode::Immediate, Read(Cpu::nop_read), 2, false),
+    /* E3 */ Opcode::illegal("ISC", Mode::IndexedIndirect, Modify(Cpu::isc), 8, false),
+    /* E4 */ Opcode::legal("CPX", Mode::ZeroPage, Read(Cpu::cpx), 3, false),
+    /* E5 */ Opcode::legal("SBC", Mode::ZeroPage, Read(Cpu::sbc), 3, false),
+    /* E6 */ Opcode::legal("INC", Mode::ZeroPage, Modify(Cpu::inc), 5, false),
+    /* E7 */ Opcode::illegal("ISC", Mode::ZeroPage, Modify(Cpu::isc), 5, false),
+    /* E8 */ Opcode::legal("INX", Mode::Implied, Implied(Cpu::inx), 2, false),
+    /* E9 */ Opcode::legal("SBC", Mode::Immediate, Read(Cpu::sbc), 2, false),
+    /* EA */ Opcode::legal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* EB */ Opcode::illegal("SBC", Mode::Immediate, Read(Cpu::sbc), 2, false),
+    /* EC */ Opcode::legal("CPX", Mode::Absolute, Read(Cpu::cpx), 4, false),
+    /* ED */ Opcode::legal("SBC", Mode::Absolute, Read(Cpu::sbc), 4, false),
+    /* EE */ Opcode::legal("INC", Mode::Absolute, Modify(Cpu::inc), 6, false),
+    /* EF */ Opcode::illegal("ISC", Mode::Absolute, Modify(Cpu::isc), 6, false),
+    /* F0 */ Opcode::legal("BEQ", Mode::Relative, Branch(Cpu::beq), 2, true),
+    /* F1 */ Opcode::legal("SBC", Mode::IndirectIndexed, Read(Cpu::sbc), 5, true),
+    /* F2 */ Opcode::illegal("JAM", Mode::Implied, Jam, 2, false),
+    /* F3 */ Opcode::illegal("ISC", Mode::IndirectIndexed, Modify(Cpu::isc), 8, false),
+    /* F4 */ Opcode::illegal("NOP", Mode::ZeroPageX, Read(Cpu::nop_read), 4, false),
+    /* F5 */ Opcode::legal("SBC", Mode::ZeroPageX, Read(Cpu::sbc), 4, false),
+    /* F6 */ Opcode::legal("INC", Mode::ZeroPageX, Modify(Cpu::inc), 6, false),
+    /* F7 */ Opcode::illegal("ISC", Mode::ZeroPageX, Modify(Cpu::isc), 6, false),
+    /* F8 */ Opcode::legal("SED", Mode::Implied, Implied(Cpu::sed), 2, false),
+    /* F9 */ Opcode::legal("SBC", Mode::AbsoluteY, Read(Cpu::sbc), 4, true),
+    /* FA */ Opcode::illegal("NOP", Mode::Implied, Implied(Cpu::nop), 2, false),
+    /* FB */ Opcode::illegal("ISC", Mode::AbsoluteY, Modify(Cpu::isc), 7, false),
+    /* FC */ Opcode::illegal("NOP", Mode::AbsoluteX, Read(Cpu::nop_read), 4, true),
+    /* FD */ Opcode::legal("SBC", Mode::AbsoluteX, Read(Cpu::sbc), 4, true),
+    /* FE */ Opcode::legal("INC", Mode::AbsoluteX, Modify(Cpu::inc), 7, false),
+    /* FF */ Opcode::illegal("ISC", Mode::AbsoluteX, Modify(Cpu::isc), 7, false),
+];
+
+/// Formats the instruction at `pc`, `bytes` holds the opcode and as many operand bytes as it uses.
+/// Illegal opcodes are marked with a `*`.
+pub fn disassemble(pc: u16, bytes: [u8; 3]) -> String {
+    let opcode = &OPCODES[bytes[0] as usize];
+    let word = u16::from_le_bytes([bytes[1], bytes[2]]);
+    let operand = match opcode.mode {
+        Mode::Implied => String::new(),
+        Mode::Accumulator => String::from("A"),
+        Mode::Immediate => format!("#${:02X}", bytes[1]),
+        Mode::ZeroPage => format!("${:02X}", bytes[1]),
+        Mode::ZeroPageX => format!("${:02X},X", bytes[1]),
+        Mode::ZeroPageY => format!("${:02X},Y", bytes[1]),
+        Mode::Absolute => format!("${:04X}", word),
+        Mode::AbsoluteX => format!("${:04X},X", word),
+        Mode::AbsoluteY => format!("${:04X},Y", word),
+        Mode::Indirect => format!("(${:04X})", word),
+        Mode::IndexedIndirect => format!("(${:02X},X)", bytes[1]),
+        Mode::IndirectIndexed => format!("(${:02X}),Y", bytes[1]),
+        Mode::Relative => format!("${:04X}", pc.wrapping_add(2).wrapping_add_signed(bytes[1] as i8 as i16)),
+    };
+    let mnemonic = if opcode.legal { opcode.mnemonic.to_string() } else { format!("*{}", opcode.mnemonic) };
+    if operand.is_empty() {
+        mnemonic
+    } else {
+        format!("{} {}", mnemonic, operand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::addressing::Addressing;
+    use crate::emulator::cpu::Cpu;
+    use crate::emulator::cpu_tests::{Access, FlatRam};
+    use crate::emulator::logger::CpuLogger;
+
+    /// Runs one instruction with memory, X and Y all set to `fill`: 0 never crosses a page, $FF
+    /// always does. Returns the cycles taken and the cycles that wrote.
+    fn run(opcode: u8, p: u8, fill: u8) -> (u8, u16) {
+        let mut ram = FlatRam::new(vec![fill; 0x10000]);
+        ram.data[0x0200] = opcode;
+        ram.accesses = Some(Vec::new());
+        let mut cpu = Cpu::new();
+        cpu.pc = 0x0200;
+        cpu.sp = 0xFD;
+        cpu.p = p;
+        cpu.x = fill;
+        cpu.y = fill;
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        let (mut ticks, mut writes) = (0, 0);
+        loop {
+            let sub_tick = cpu.state;
+            cpu.tick(&mut logger, &mut ram, &mut addressing).unwrap();
+            ticks += 1;
+            let accesses = ram.accesses.replace(Vec::new()).unwrap();
+            if accesses.iter().any(|access| matches!(access, Access::Write(..))) {
+                writes |= 1 << sub_tick;
+            }
+            if cpu.state == 1 {
+                return (ticks, writes);
+            }
+        }
+    }
+
+    fn ticks(opcode: u8, p: u8, fill: u8) -> u8 {
+        run(opcode, p, fill).0
+    }
+
+    #[test]
+    fn cycles_match_the_cpu() {
+        for (opcode, entry) in OPCODES.iter().enumerate() {
+            if matches!(entry.operation, Jam) {
+                continue;
+            }
+            // a branch by 0 is not taken with one of the two flag settings
+            let ticks = ticks(opcode as u8, 0x24, 0).min(ticks(opcode as u8, 0xE7, 0));
+            assert_eq!(ticks, entry.cycles, "opcode {:02X} {}", opcode, entry.mnemonic);
+        }
+    }
+
+    #[test]
+    fn page_crossing_matches_the_cpu() {
+        for (opcode, entry) in OPCODES.iter().enumerate() {
+            if matches!(entry.operation, Jam) || entry.mode == Mode::Relative {
+                continue;
+            }
+            let ticks = ticks(opcode as u8, 0x24, 0xFF).max(ticks(opcode as u8, 0xE7, 0xFF));
+            assert_eq!(ticks, entry.cycles + entry.page_cross as u8, "opcode {:02X} {}", opcode, entry.mnemonic);
+        }
+    }
+
+    #[test]
+    fn writes_match_the_cpu() {
+        for (opcode, entry) in OPCODES.iter().enumerate() {
+            if matches!(entry.operation, Jam) {
+                continue;
+            }
+            for (p, fill) in [(0x24, 0), (0xE7, 0), (0x24, 0xFF), (0xE7, 0xFF)] {
+                let (_, writes) = run(opcode as u8, p, fill);
+                assert_eq!(writes, entry.writes(), "opcode {:02X} {}", opcode, entry.mnemonic);
+            }
+        }
+    }
+
+    #[test]
+    fn disassembly() {
+        assert_eq!(disassemble(0xC000, [0xBD, 0x34, 0x12]), "LDA $1234,X");
+        assert_eq!(disassemble(0xC000, [0xD0, 0xFE, 0x00]), "BNE $C000");
+        assert_eq!(disassemble(0xC000, [0x0A, 0x00, 0x00]), "ASL A");
+        assert_eq!(disassemble(0xC000, [0xB3, 0x20, 0x00]), "*LAX ($20),Y");
+    }
+}
@@ -0,0 +1,330 @@
+use crate::emulator::bus::Bus;
+use crate::emulator::expansion_port::ExpansionPort;
+
+const MIN_SIZE: usize = 0x20000;
+const MAX_SIZE: usize = 0x1000000;
+
+const STATUS_IRQ: u8 = 0x80;
+const STATUS_END_OF_BLOCK: u8 = 0x40;
+const STATUS_FAULT: u8 = 0x20;
+/// The 1764 and 1750 have 256K chips, the 1700 has 64K chips.
+const STATUS_SIZE: u8 = 0x10;
+
+const COMMAND_EXECUTE: u8 = 0x80;
+const COMMAND_AUTOLOAD: u8 = 0x20;
+/// Starts right away instead of waiting for a write to $FF00.
+const COMMAND_FF00_DISABLED: u8 = 0x10;
+
+const STASH: u8 = 0x00;
+const FETCH: u8 = 0x01;
+const SWAP: u8 = 0x02;
+
+const MASK_IRQ: u8 = 0x80;
+const MASK_END_OF_BLOCK: u8 = 0x40;
+const MASK_FAULT: u8 = 0x20;
+
+const FIX_C64_ADDRESS: u8 = 0x80;
+const FIX_REU_ADDRESS: u8 = 0x40;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum State {
+    Idle,
+    /// Waiting for a write to $FF00.
+    Armed,
+    /// The command was written in this cycle, the DMA starts in the next one.
+    Starting,
+    Transferring,
+}
+
+/// The addresses and length, the REC keeps a second copy of them for autoload.
+#[derive(Clone, Copy)]
+struct Transfer {
+    c64_address: u16,
+    reu_address: u32,
+    length: u16,
+}
+
+/// A 1700, 1764 or 1750 RAM Expansion Unit, or one of the larger clones, with the registers of
+/// its REC (RAM Expansion Controller) at $DF00, mirrored every 32 bytes in I/O2.
+///
+/// Transfers hold the DMA line and move one byte per cycle, swaps take two. They pause while the
+/// VIC holds BA low.
+pub struct Reu {
+    ram: Vec<u8>,
+    state: State,
+    status: u8,
+    command: u8,
+    transfer: Transfer,
+    shadow: Transfer,
+    interrupt_mask: u8,
+    address_control: u8,
+    /// The C64 byte of a swap, read in the first of its two cycles.
+    swap_latch: Option<u8>,
+}
+
+impl Reu {
+    /// `size` in bytes, a power of two from 128K to 16MB.
+    pub fn new(size: usize) -> Result<Reu, String> {
+        if !size.is_power_of_two() || !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            return Err(format!("Unsupported REU size {}K", size / 1024));
+        }
+        let transfer = Transfer { c64_address: 0, reu_address: 0, length: 0xFFFF };
+        Ok(Reu {
+            ram: vec![0; size],
+            state: State::Idle,
+            status: if size > MIN_SIZE { STATUS_SIZE } else { 0 },
+            command: COMMAND_FF00_DISABLED,
+            transfer,
+            shadow: transfer,
+            interrupt_mask: 0,
+            address_control: 0,
+            swap_latch: None,
+        })
+    }
+
+    fn read(&mut self, loc: usize) -> u8 {
+        match loc & 0x1F {
+            0x00 => {
+                let status = self.status;
+                self.status &= !(STATUS_IRQ | STATUS_END_OF_BLOCK | STATUS_FAULT);
+                status
+            }
+            0x01 => self.command,
+            0x02 => self.transfer.c64_address as u8,
+            0x03 => (self.transfer.c64_address >> 8) as u8,
+            0x04 => self.transfer.reu_address as u8,
+            0x05 => (self.transfer.reu_address >> 8) as u8,
+            // the REC has three bank bits, larger units decode all of them
+            0x06 if self.ram.len() <= 0x80000 => (self.transfer.reu_address >> 16) as u8 | 0xF8,
+            0x06 => (self.transfer.reu_address >> 16) as u8,
+            0x07 => self.transfer.length as u8,
+            0x08 => (self.transfer.length >> 8) as u8,
+            0x09 => self.interrupt_mask | 0x1F,
+            0x0A => self.address_control | 0x3F,
+            _ => 0xFF,
+        }
+    }
+
+    fn write(&mut self, loc: usize, value: u8) {
+        match loc & 0x1F {
+            0x01 => {
+                self.command = value;
+                if value & COMMAND_EXECUTE != 0 {
+                    self.state = if value & COMMAND_FF00_DISABLED != 0 { State::Starting } else { State::Armed };
+                }
+            }
+            0x02 => self.transfer.c64_address = (self.transfer.c64_address & 0xFF00) | value as u16,
+            0x03 => self.transfer.c64_address = (self.transfer.c64_address & 0x00FF) | ((value as u16) << 8),
+            0x04 => self.transfer.reu_address = (self.transfer.reu_address & 0xFFFF00) | value as u32,
+            0x05 => self.transfer.reu_address = (self.transfer.reu_address & 0xFF00FF) | ((value as u32) << 8),
+            0x06 => self.transfer.reu_address = (self.transfer.reu_address & 0x00FFFF) | ((value as u32) << 16),
+            0x07 => self.transfer.length = (self.transfer.length & 0xFF00) | value as u16,
+            0x08 => self.transfer.length = (self.transfer.length & 0x00FF) | ((value as u16) << 8),
+            0x09 => {
+                self.interrupt_mask = value & (MASK_IRQ | MASK_END_OF_BLOCK | MASK_FAULT);
+                self.update_irq();
+            }
+            0x0A => self.address_control = value & (FIX_C64_ADDRESS | FIX_REU_ADDRESS),
+            _ => {}
+        }
+        // writes load both copies of the transfer registers
+        if (0x02..=0x08).contains(&(loc & 0x1F)) {
+            self.shadow = self.transfer;
+        }
+    }
+
+    fn transfer_byte(&mut self, c64: &mut dyn Bus) {
+        let c64_address = self.transfer.c64_address;
+        let reu_address = self.transfer.reu_address as usize & (self.ram.len() - 1);
+        match self.command & 0x03 {
+            STASH => self.ram[reu_address] = c64.read(c64_address),
+            FETCH => c64.write(c64_address, self.ram[reu_address]),
+            SWAP => match self.swap_latch.take() {
+                None => {
+                    self.swap_latch = Some(c64.read(c64_address));
+                    return;
+                }
+                Some(value) => {
+                    c64.write(c64_address, self.ram[reu_address]);
+                    self.ram[reu_address] = value;
+                }
+            },
+            _ => {
+                if c64.read(c64_address) != self.ram[reu_address] {
+                    self.status |= STATUS_FAULT;
+                    self.finish();
+                    return;
+                }
+            }
+        }
+
+        if self.address_control & FIX_C64_ADDRESS == 0 {
+            self.transfer.c64_address = c64_address.wrapping_add(1);
+        }
+        if self.address_control & FIX_REU_ADDRESS == 0 {
+            self.transfer.reu_address = (self.transfer.reu_address + 1) & 0xFFFFFF;
+        }
+        // a length of 0 is 64K
+        if self.transfer.length == 1 {
+            self.status |= STATUS_END_OF_BLOCK;
+            self.finish();
+        } else {
+            self.transfer.length = self.transfer.length.wrapping_sub(1);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.state = State::Idle;
+        self.command = (self.command & !COMMAND_EXECUTE) | COMMAND_FF00_DISABLED;
+        if self.command & COMMAND_AUTOLOAD != 0 {
+            self.transfer = self.shadow;
+        }
+        self.update_irq();
+    }
+
+    fn update_irq(&mut self) {
+        let end_of_block = self.interrupt_mask & MASK_END_OF_BLOCK != 0 && self.status & STATUS_END_OF_BLOCK != 0;
+        let fault = self.interrupt_mask & MASK_FAULT != 0 && self.status & STATUS_FAULT != 0;
+        if self.interrupt_mask & MASK_IRQ != 0 && (end_of_block || fault) {
+            self.status |= STATUS_IRQ;
+        }
+    }
+}
+
+impl ExpansionPort for Reu {
+    fn read_io2(&mut self, loc: usize) -> Option<u8> {
+        Some(self.read(loc))
+    }
+
+    fn write_io2(&mut self, loc: usize, value: u8) {
+        self.write(loc, value);
+    }
+
+    /// A write to $FF00 starts an armed transfer.
+    fn snoop_write(&mut self, address: u16, _value: u8) {
+        if address == 0xFF00 && self.state == State::Armed {
+            self.state = State::Starting;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.status & STATUS_IRQ != 0
+    }
+
+    fn dma(&self) -> bool {
+        self.state == State::Transferring
+    }
+
+    /// Moves a byte between the C64 and the REU while a transfer is going on.
+    fn tick(&mut self, c64: &mut dyn Bus, ba_low: bool) {
+        match self.state {
+            State::Starting => self.state = State::Transferring,
+            State::Transferring if !ba_low => self.transfer_byte(c64),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::addressing::Addressing;
+    use crate::emulator::cpu::Cpu;
+    use crate::emulator::cpu_tests::FlatRam;
+    use crate::emulator::logger::CpuLogger;
+    use crate::emulator::memory::Memory;
+    use crate::emulator::roms::Roms;
+
+    /// Sets up a transfer of `length` bytes between $1000 and REU $010000 and runs it to the end,
+    /// returning the number of DMA cycles.
+    fn run(reu: &mut Reu, c64: &mut FlatRam, command: u8, length: u16) -> usize {
+        for (register, value) in [(2, 0x00), (3, 0x10), (4, 0x00), (5, 0x00), (6, 0x01), (7, length as u8), (8, (length >> 8) as u8)] {
+            reu.write(0xDF00 + register, value);
+        }
+        reu.write(0xDF01, COMMAND_EXECUTE | COMMAND_FF00_DISABLED | command);
+        reu.tick(c64, false);
+        let mut cycles = 0;
+        while reu.dma() {
+            reu.tick(c64, false);
+            cycles += 1;
+        }
+        cycles
+    }
+
+    #[test]
+    fn stash_fetch_swap_verify() {
+        let mut reu = Reu::new(0x80000).unwrap();
+        let mut c64 = FlatRam::new(vec![0; 0x10000]);
+        c64.data[0x1000..0x1004].copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(run(&mut reu, &mut c64, STASH, 4), 4);
+        assert_eq!(&reu.ram[0x10000..0x10004], &[1, 2, 3, 4]);
+        assert_eq!(reu.read(0xDF00), STATUS_END_OF_BLOCK | STATUS_SIZE);
+        assert_eq!(reu.read(0xDF00), STATUS_SIZE, "reading clears the status");
+        assert_eq!(reu.read(0xDF02), 0x04, "addresses are left past the block");
+        assert_eq!(reu.read(0xDF07), 0x01);
+
+        c64.data[0x1000..0x1004].fill(0);
+        assert_eq!(run(&mut reu, &mut c64, FETCH, 4), 4);
+        assert_eq!(&c64.data[0x1000..0x1004], &[1, 2, 3, 4]);
+
+        c64.data[0x1000..0x1002].copy_from_slice(&[8, 9]);
+        assert_eq!(run(&mut reu, &mut c64, SWAP, 2), 4, "swaps take two cycles per byte");
+        assert_eq!(&c64.data[0x1000..0x1002], &[1, 2]);
+        assert_eq!(&reu.ram[0x10000..0x10002], &[8, 9]);
+
+        assert_eq!(run(&mut reu, &mut c64, 0x03, 2), 1, "verify stops at the first difference");
+        assert_eq!(reu.read(0xDF00) & STATUS_FAULT, STATUS_FAULT);
+    }
+
+    #[test]
+    fn ff00_trigger_autoload_and_irq() {
+        let mut reu = Reu::new(0x20000).unwrap();
+        let mut c64 = FlatRam::new(vec![0x55; 0x10000]);
+        reu.write(0xDF09, MASK_IRQ | MASK_END_OF_BLOCK);
+        reu.write(0xDF07, 0x10);
+        reu.write(0xDF08, 0x00);
+        reu.write(0xDF01, COMMAND_EXECUTE | COMMAND_AUTOLOAD);
+        reu.tick(&mut c64, false);
+        assert!(!reu.dma(), "waits for $FF00");
+        reu.snoop_write(0xFF00, 0x00);
+        reu.tick(&mut c64, false);
+        for _ in 0..0x10 {
+            assert!(reu.dma());
+            reu.tick(&mut c64, true);
+            reu.tick(&mut c64, false);
+        }
+        assert!(!reu.dma());
+        assert!(reu.irq());
+        assert_eq!(reu.read(0xDF07), 0x10, "autoload restores the length");
+        assert_eq!(reu.read(0xDF06), 0xF8);
+        assert_eq!(reu.ram[0x0F], 0x55);
+        assert_eq!(reu.read(0xDF00) & STATUS_IRQ, STATUS_IRQ);
+        assert!(!reu.irq());
+    }
+
+    #[test]
+    fn dma_halts_the_cpu() {
+        let mut memory = Memory::new(Roms::new());
+        memory.attach(Box::new(Reu::new(0x20000).unwrap()));
+        // a 16 byte stash, then NOP
+        let program = [0xA9, 0x10, 0x8D, 0x07, 0xDF, 0xA9, 0x00, 0x8D, 0x08, 0xDF, 0xA9, 0x90, 0x8D, 0x01, 0xDF, 0xEA];
+        for (i, value) in program.iter().enumerate() {
+            memory.write(0xC000 + i as u16, *value);
+        }
+        let mut cpu = Cpu::new();
+        cpu.pc = 0xC000;
+        let mut logger = CpuLogger::new();
+        let mut addressing = Addressing::new();
+        while cpu.pc != 0xC00F || cpu.state != 1 {
+            cpu.tick(&mut logger, &mut memory, &mut addressing).unwrap();
+        }
+        for _ in 0..16 {
+            assert!(memory.dma());
+            cpu.tick(&mut logger, &mut memory, &mut addressing).unwrap();
+        }
+        assert_eq!(cpu.pc, 0xC00F);
+        assert!(!memory.dma());
+        cpu.tick(&mut logger, &mut memory, &mut addressing).unwrap();
+        assert_eq!(cpu.pc, 0xC010);
+    }
+}
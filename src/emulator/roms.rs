@@ -0,0 +1,126 @@
+use std::fs;
+
+const BASIC: &[u8] = include_bytes!("memory/basic.901226-01.bin");
+const KERNAL: &[u8] = include_bytes!("memory/kernal.901227-03.bin");
+const CHARACTERS: &[u8] = include_bytes!("memory/characters.901225-01.bin");
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RomKind {
+    Basic,
+    Kernal,
+    Characters,
+}
+
+impl RomKind {
+    pub fn size(self) -> usize {
+        match self {
+            RomKind::Basic | RomKind::Kernal => 0x2000,
+            RomKind::Characters => 0x1000,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RomKind::Basic => "BASIC",
+            RomKind::Kernal => "KERNAL",
+            RomKind::Characters => "CHARGEN",
+        }
+    }
+}
+
+/// Dumps recognised by their CRC32.
+static KNOWN_ROMS: [(RomKind, u32, &str); 7] = [
+    (RomKind::Basic, 0xF833D117, "901226-01"),
+    (RomKind::Kernal, 0xDCE782FA, "901227-01"),
+    (RomKind::Kernal, 0xA5C687B3, "901227-02"),
+    (RomKind::Kernal, 0xDBE3E7C7, "901227-03"),
+    (RomKind::Kernal, 0x2C5965D4, "251104-04 (SX-64)"),
+    (RomKind::Kernal, 0x2F79984C, "JiffyDOS 6.01"),
+    (RomKind::Characters, 0xEC4272EE, "901225-01"),
+];
+
+pub struct Rom {
+    data: Vec<u8>,
+    revision: String,
+}
+
+impl Rom {
+    /// Accepts any image of the right size, unknown ones are reported by their checksum.
+    pub fn from_bytes(kind: RomKind, data: Vec<u8>) -> Result<Rom, String> {
+        if data.len() != kind.size() {
+            return Err(format!("{} ROM must be {} bytes, got {}", kind.name(), kind.size(), data.len()));
+        }
+        let crc = crc32(&data);
+        let revision = match KNOWN_ROMS.iter().find(|(k, c, _)| *k == kind && *c == crc) {
+            Some((_, _, revision)) => revision.to_string(),
+            None => format!("unknown (CRC32 {:08X})", crc),
+        };
+        Ok(Rom { data, revision })
+    }
+
+    pub fn load(kind: RomKind, path: &str) -> Result<Rom, String> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Error loading {} ROM '{}': {}", kind.name(), path, e))?;
+        Rom::from_bytes(kind, data)
+    }
+
+    pub fn revision(&self) -> &str {
+        &self.revision
+    }
+
+    pub fn get(&self, offset: usize) -> u8 {
+        self.data[offset]
+    }
+}
+
+pub struct Roms {
+    pub basic: Rom,
+    pub kernal: Rom,
+    pub characters: Rom,
+}
+
+impl Roms {
+    /// The ROMs of a stock PAL C64.
+    pub fn new() -> Roms {
+        Roms {
+            basic: Rom::from_bytes(RomKind::Basic, BASIC.to_vec()).unwrap(),
+            kernal: Rom::from_bytes(RomKind::Kernal, KERNAL.to_vec()).unwrap(),
+            characters: Rom::from_bytes(RomKind::Characters, CHARACTERS.to_vec()).unwrap(),
+        }
+    }
+
+    pub fn replace(&mut self, kind: RomKind, rom: Rom) {
+        match kind {
+            RomKind::Basic => self.basic = rom,
+            RomKind::Kernal => self.kernal = rom,
+            RomKind::Characters => self.characters = rom,
+        }
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identification() {
+        let roms = Roms::new();
+        assert_eq!(roms.kernal.revision(), "901227-03");
+        assert_eq!(roms.characters.revision(), "901225-01");
+
+        let rom = Rom::from_bytes(RomKind::Kernal, vec![0; 0x2000]).unwrap();
+        assert_eq!(rom.revision(), "unknown (CRC32 D8F49994)");
+        assert!(Rom::from_bytes(RomKind::Characters, vec![0; 0x2000]).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+//! Per-cycle bus validation against the SingleStepTests 6502 vectors
+//! (https://github.com/SingleStepTests/65x02, `6502/v1`). The vectors are not part of the
+//! repository, so the test is ignored by default. Fetch them and run `cargo test -- --ignored`:
+//!
+//! ```text
+//! git clone --depth 1 https://github.com/SingleStepTests/65x02
+//! mkdir -p tests/single_step
+//! cp -r 65x02/6502/v1 tests/single_step/6502
+//! ```
+//!
+//! Every opcode that is not skipped needs its `00.json` .. `ff.json` file.
+//!
+//! Every vector executes one instruction and is checked against the final registers and RAM, and
+//! against the address, value and direction of every bus cycle, dummy accesses included.
+//!
+//! `tests/single_step/handwritten.json` is in the repository and always runs. Its vectors use the
+//! same format, but were written from the cycle tables in 64doc, one for each kind of bus sequence.
+
+use std::fs;
+use std::path::PathBuf;
+
+use serde_json::Value;
+
+use crate::emulator::addressing::Addressing;
+use crate::emulator::cpu::Cpu;
+use crate::emulator::cpu_tests::{Access, FlatRam};
+use crate::emulator::logger::CpuLogger;
+
+/// JAM stops the CPU, and ANE/LXA depend on a chip specific constant.
+const SKIPPED_OPCODES: [u8; 14] = [0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2, 0x8B, 0xAB];
+const MAX_FAILURES: usize = 10;
+
+fn number(value: &Value, name: &str) -> u64 {
+    value[name].as_u64().unwrap_or_else(|| panic!("missing '{}'", name))
+}
+
+fn cycles(test: &Value) -> Vec<Access> {
+    test["cycles"].as_array().unwrap().iter().map(|cycle| {
+        let address = cycle[0].as_u64().unwrap() as u16;
+        let value = cycle[1].as_u64().unwrap() as u8;
+        if cycle[2].as_str().unwrap() == "write" {
+            Access::Write(address, value)
+        } else {
+            Access::Read(address, value)
+        }
+    }).collect()
+}
+
+/// Executes one vector and returns a description of the first difference.
+fn run_vector(test: &Value) -> Result<(), String> {
+    let initial = &test["initial"];
+    let expected = &test["final"];
+    let mut ram = FlatRam::new(vec![0; 0x10000]);
+    ram.accesses = Some(Vec::new());
+    for entry in initial["ram"].as_array().unwrap() {
+        ram.data[entry[0].as_u64().unwrap() as usize] = entry[1].as_u64().unwrap() as u8;
+    }
+    let mut cpu = Cpu::new();
+    cpu.pc = number(initial, "pc") as u16;
+    cpu.sp = number(initial, "s") as u8;
+    cpu.a = number(initial, "a") as u8;
+    cpu.x = number(initial, "x") as u8;
+    cpu.y = number(initial, "y") as u8;
+    cpu.p = number(initial, "p") as u8;
+
+    let mut logger = CpuLogger::new();
+    let mut addressing = Addressing::new();
+    cpu.tick(&mut logger, &mut ram, &mut addressing)?;
+    let mut ticks = 1;
+    while cpu.state != 1 {
+        cpu.tick(&mut logger, &mut ram, &mut addressing)?;
+        ticks += 1;
+        if ticks > 10 {
+            return Err(String::from("instruction did not finish in 10 cycles"));
+        }
+    }
+
+    let expected_cycles = cycles(test);
+    let accesses = ram.accesses.take().unwrap();
+    for (i, expected_cycle) in expected_cycles.iter().enumerate() {
+        match accesses.get(i) {
+            Some(access) if access == expected_cycle => {}
+            access => return Err(format!("cycle {}: expected {:?}, got {:?}", i + 1, expected_cycle, access)),
+        }
+    }
+    if accesses.len() != expected_cycles.len() || ticks != expected_cycles.len() {
+        return Err(format!("expected {} cycles, got {} ticks and {} bus accesses", expected_cycles.len(), ticks, accesses.len()));
+    }
+
+    let registers = [
+        ("pc", cpu.pc as u64, number(expected, "pc")),
+        ("s", cpu.sp as u64, number(expected, "s")),
+        ("a", cpu.a as u64, number(expected, "a")),
+        ("x", cpu.x as u64, number(expected, "x")),
+        ("y", cpu.y as u64, number(expected, "y")),
+        // B and bit 5 only exist on the stack
+        ("p", (cpu.p & 0xCF) as u64, number(expected, "p") & 0xCF),
+    ];
+    for (name, actual, expected) in registers {
+        if actual != expected {
+            return Err(format!("{}: expected {:02X}, got {:02X}", name, expected, actual));
+        }
+    }
+    for entry in expected["ram"].as_array().unwrap() {
+        let address = entry[0].as_u64().unwrap() as usize;
+        let value = entry[1].as_u64().unwrap() as u8;
+        if ram.data[address] != value {
+            return Err(format!("ram ${:04X}: expected {:02X}, got {:02X}", address, value, ram.data[address]));
+        }
+    }
+    Ok(())
+}
+
+fn read_vectors(path: &PathBuf) -> Value {
+    let json = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn handwritten_vectors() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "single_step", "handwritten.json"].iter().collect();
+    let failures: Vec<String> = read_vectors(&path).as_array().unwrap().iter()
+        .filter_map(|test| run_vector(test).err().map(|e| format!("'{}': {}", test["name"].as_str().unwrap_or(""), e)))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+#[ignore = "needs the vectors in tests/single_step/6502"]
+fn single_step_tests() {
+    let directory: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "single_step", "6502"].iter().collect();
+    assert!(directory.is_dir(), "{} not found", directory.display());
+    let mut failures = Vec::new();
+    for opcode in 0..=0xFFu8 {
+        if SKIPPED_OPCODES.contains(&opcode) {
+            continue;
+        }
+        let tests = read_vectors(&directory.join(format!("{:02x}.json", opcode)));
+        for test in tests.as_array().unwrap() {
+            if let Err(e) = run_vector(test) {
+                failures.push(format!("{:02X} '{}': {}", opcode, test["name"].as_str().unwrap_or(""), e));
+                // one failure per opcode is enough to see what is wrong
+                break;
+            }
+        }
+        if failures.len() >= MAX_FAILURES {
+            break;
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -1,5 +1,12 @@
-use crate::emulator::cpu::Cpu;
-use crate::emulator::memory::cia1::Cia1;
+/// Timer A registers of a CIA, the counting itself is done by `TimerA`.
+pub trait TimerARegisters {
+    fn timer_a_start_value_low(&self) -> u8;
+    fn timer_a_start_value_high(&self) -> u8;
+    fn timer_a_control(&self) -> u8;
+    fn stop_timer_a(&mut self);
+    /// Latches the underflow in the interrupt data register.
+    fn interrupt_timer_a(&mut self);
+}
 
 pub struct TimerA {
     control_register_address: u16,
@@ -22,15 +29,15 @@ impl TimerA {
         }
     }
 
-    pub fn tick(&mut self, cpu: &mut Cpu, cia1: &mut Cia1)  {
+    pub fn tick(&mut self, cia: &mut impl TimerARegisters) {
         if self.clear_port_b {
-            //todo revert cia1.port_b_read_and(!0x40);
+            //todo revert cia.port_b_read_and(!0x40);
             //debug!("port b low");
             self.clear_port_b = false;
         }
-        if cia1.timer_a_control() & 0x10 == 0x10 {
+        if cia.timer_a_control() & 0x10 == 0x10 {
             if !self.latch_loaded {
-                self.value = (cia1.timer_a_start_value_low() as u16) | ((cia1.timer_a_start_value_high() as u16) << 8);
+                self.value = (cia.timer_a_start_value_low() as u16) | ((cia.timer_a_start_value_high() as u16) << 8);
                 //debug!("start value loaded initially {:04X}", self.value);
                 self.latch_loaded = true;
             }
@@ -40,28 +47,21 @@ impl TimerA {
             }
         }
 
-        if cia1.timer_a_control() & 0x01 == 0x01 {
+        if cia.timer_a_control() & 0x01 == 0x01 {
             //timer is active
             if self.value == 0 {
-                if cia1.timer_a_control() & 0x02 == 0x02 {
-                    panic!("timer a underflow")
-                }
-                if cia1.timer_a_control() & 0x04 == 0x04 {
-                    panic!("timer a underflow")
-                } else {
-                    //todo revert cia1.port_b_read_or(0x40);
+                // the underflow is not put out on PB6 (bits 1 and 2), nothing is wired to it
+                if cia.timer_a_control() & 0x04 == 0 {
+                    //todo revert cia.port_b_read_or(0x40);
                     //debug!("port b high");
                     self.clear_port_b = true;
                 }
-                if cia1.timer_a_control() & 0x08 == 0x08 {
-                    panic!("timer a underflow")
-                } else {
-                    self.value = (cia1.timer_a_start_value_low() as u16) | ((cia1.timer_a_start_value_high() as u16) << 8);
-                    //debug!("start value loaded {:04X}", self.value);
-                }
-                if cia1.interrupt_timer_a() {
-                    cpu.interrupt();
+                self.value = (cia.timer_a_start_value_low() as u16) | ((cia.timer_a_start_value_high() as u16) << 8);
+                //debug!("start value loaded {:04X}", self.value);
+                if cia.timer_a_control() & 0x08 == 0x08 {
+                    cia.stop_timer_a();
                 }
+                cia.interrupt_timer_a();
             } else {
                 self.value -= 1;
             }
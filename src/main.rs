@@ -1,18 +1,24 @@
 use std::time::SystemTime;
 
 use env_logger::Env;
-use log::info;
+use log::{info, warn};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::video::FullscreenType;
 
 use crate::emulator::emulator::Emulator;
+use crate::emulator::gpu;
 use crate::emulator::keyboard::Key;
+use crate::emulator::cartridge::Cartridge;
+use crate::emulator::cartridge::crt::Crt;
+use crate::emulator::georam::GeoRam;
+use crate::emulator::reu::Reu;
+use crate::emulator::roms::{Rom, RomKind, Roms};
 
-const DEFAULT_SCREEN_SCALE: u32 = 4;
-const SCREEN_WIDTH: u32 = 320;
-const SCREEN_HEIGHT: u32 = 200;
+const DEFAULT_SCREEN_SCALE: u32 = 3;
+const SCREEN_WIDTH: u32 = gpu::SCREEN_WIDTH as u32;
+const SCREEN_HEIGHT: u32 = gpu::SCREEN_HEIGHT as u32;
 
 mod emulator;
 
@@ -67,11 +73,30 @@ pub fn main() -> Result<(), String> {
     // let f_name = "/home/vanja/___devel/emulator/roms/games/Blinky [Hans Christian Egeberg, 1991].ch8";
     // let vec = fs::read(f_name)
     //     .map_err(|e| format!("Error loading file '{}': {}", f_name, e.to_string()))?;
-    let mut emulator = Emulator::new();
+    let arguments = parse_arguments()?;
+    let roms = arguments.roms;
+    info!("BASIC {}, KERNAL {}, CHARGEN {}", roms.basic.revision(), roms.kernal.revision(), roms.characters.revision());
+    let mut emulator = Emulator::new(roms);
+    if let Some(reu) = arguments.reu {
+        emulator.attach(Box::new(reu));
+    }
+    if arguments.georam_size.is_some() || arguments.georam_path.is_some() {
+        let size = arguments.georam_size.unwrap_or(512 * 1024);
+        let georam = match &arguments.georam_path {
+            Some(path) => GeoRam::load(path, size)?,
+            None => GeoRam::new(size)?,
+        };
+        emulator.attach(Box::new(georam));
+    }
+    if let Some(cartridge) = arguments.cartridge {
+        info!("cartridge '{}'", cartridge.name());
+        emulator.attach(Box::new(cartridge));
+    }
 
     canvas.present();
     let mut event_pump = sdl_context.event_pump()?;
     let start_time = SystemTime::now();
+    let mut jammed = false;
     canvas.present();
 
     // let audio_subsystem = sdl_context.audio().unwrap();
@@ -95,6 +120,7 @@ pub fn main() -> Result<(), String> {
             match event {
                 Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } =>
                     break 'running,
+                Event::KeyDown { keycode: Some(Keycode::F12), repeat: false, .. } => emulator.freeze_cartridge(),
                 Event::KeyDown { keycode: Some(Keycode::F11), .. } => {
                     let fullscreen_type = canvas.window().fullscreen_state();
                     canvas.window_mut().set_fullscreen(if fullscreen_type == FullscreenType::Off {
@@ -115,6 +141,11 @@ pub fn main() -> Result<(), String> {
 
         let elapsed = SystemTime::now().duration_since(start_time).unwrap();
         emulator.step(elapsed)?;
+        if emulator.is_jammed() && !jammed {
+            jammed = true;
+            warn!("CPU jammed, reset required");
+            canvas.window_mut().set_title("Commodore64 - CPU JAM").map_err(|e| e.to_string())?;
+        }
 
         // if emulator.is_sound_active() {
         //     device.resume();
@@ -145,9 +176,73 @@ pub fn main() -> Result<(), String> {
         // start = now;
     }
 
+    if let (Some(crt), Some(path)) = (emulator.modified_cartridge(), arguments.cartridge_path) {
+        crt.save(&path)?;
+        info!("saved cartridge to '{}'", path);
+    }
+    if let (Some(georam), Some(path)) = (emulator.device::<GeoRam>(), arguments.georam_path) {
+        georam.save(&path)?;
+    }
     Ok(())
 }
 
+/// What the command line asks to plug in.
+struct Arguments {
+    roms: Roms,
+    cartridge: Option<Cartridge>,
+    /// Where flash changes of the cartridge are saved.
+    cartridge_path: Option<String>,
+    reu: Option<Reu>,
+    georam_size: Option<usize>,
+    /// Where the geoRAM contents are kept between runs.
+    georam_path: Option<String>,
+}
+
+/// Replaces the built-in ROMs with the images given as `--basic`, `--kernal` and `--chargen`,
+/// loads the CRT file given as `--cartridge`, adds an REU of `--reu` kilobytes and a geoRAM of
+/// `--georam` kilobytes, whose contents persist in `--georam-file`.
+fn parse_arguments() -> Result<Arguments, String> {
+    let mut arguments = Arguments {
+        roms: Roms::new(),
+        cartridge: None,
+        cartridge_path: None,
+        reu: None,
+        georam_size: None,
+        georam_path: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value after '{}'", arg))?;
+        let kind = match arg.as_str() {
+            "--basic" => RomKind::Basic,
+            "--kernal" => RomKind::Kernal,
+            "--chargen" => RomKind::Characters,
+            "--cartridge" => {
+                arguments.cartridge = Some(Cartridge::from_crt(Crt::load(&value)?)?);
+                arguments.cartridge_path = Some(value);
+                continue;
+            }
+            "--reu" => {
+                let size: usize = value.parse().map_err(|_| format!("Invalid REU size '{}'", value))?;
+                arguments.reu = Some(Reu::new(size * 1024)?);
+                continue;
+            }
+            "--georam" => {
+                let size: usize = value.parse().map_err(|_| format!("Invalid geoRAM size '{}'", value))?;
+                arguments.georam_size = Some(size * 1024);
+                continue;
+            }
+            "--georam-file" => {
+                arguments.georam_path = Some(value);
+                continue;
+            }
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        };
+        arguments.roms.replace(kind, Rom::load(kind, &value)?);
+    }
+    Ok(arguments)
+}
+
 fn handle_keyboard_event(emulator: &mut Emulator, keycode: Keycode, pressed: bool) {
     match keycode {
         Keycode::A => emulator.key_change(Key::A, pressed),
@@ -167,6 +262,8 @@ fn handle_keyboard_event(emulator: &mut Emulator, keycode: Keycode, pressed: boo
         Keycode::Return => emulator.key_change(Key::Return, pressed),
         Keycode::LShift => emulator.key_change(Key::LeftShift, pressed),
         Keycode::RShift => emulator.key_change(Key::RightShift, pressed),
+        Keycode::PageDown => emulator.key_change(Key::RunStop, pressed),
+        Keycode::PageUp => emulator.key_change(Key::Restore, pressed),
         _ => {}
     }
 }
\ No newline at end of file